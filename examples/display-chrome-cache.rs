@@ -1,7 +1,10 @@
 use clap::{CommandFactory, Parser};
 use std::{fmt::Debug, path::PathBuf};
 
-use chrome_cache_parser::{CCPError, CCPResult, ChromeCache};
+use chrome_cache_parser::{
+    discover::{discover_caches, CacheFormat, CacheKind},
+    CCPError, CCPResult, ChromeCache,
+};
 
 /// A simple command line tool to display the contents of a Chrome cache directory.
 #[derive(Parser, Debug)]
@@ -17,17 +20,10 @@ struct Args {
 }
 
 fn default_cache_path() -> Option<PathBuf> {
-    let home = dirs::home_dir()?;
-    let windows_path = home.join("AppData/Local/Google/Chrome/User Data/Default/Cache/Cache_Data");
-    let linux_path = home.join(".cache/google-chrome/Default/Cache");
-
-    if windows_path.exists() {
-        Some(windows_path)
-    } else if linux_path.exists() {
-        Some(linux_path)
-    } else {
-        None
-    }
+    discover_caches(&dirs::home_dir()?)
+        .into_iter()
+        .find(|cache| cache.kind == CacheKind::Http && cache.format == CacheFormat::BlockFile)
+        .map(|cache| cache.path)
 }
 
 fn main() {
@@ -0,0 +1,896 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use chrome_cache_parser::{
+    block_file::{LazyBlockFile, LazyBlockFileCacheEntry},
+    cookies,
+    discover::{self, CacheFormat, CacheKind},
+    export,
+    hash::super_fast_hash,
+    http_response::HttpResponseOptions,
+    rules,
+    search::{self, SearchOptions},
+    CCPError, CacheAddr, CacheVersion, ChromeCache, Describe, FileType,
+};
+
+/// Exit code for failures to read the cache.
+const EXIT_ERROR: u8 = 1;
+/// Exit code when no entry matches the requested key.
+const EXIT_NOT_FOUND: u8 = 3;
+/// Exit code when `check` finds problems with the cache.
+const EXIT_PROBLEMS: u8 = 4;
+
+/// Inspect and extract the contents of a Chrome cache directory.
+///
+/// Exits with 1 when the cache can't be read, 2 on usage errors, 3 when no entry matches the
+/// requested key and 4 when `check` finds problems.
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Args {
+    /// Path to the cache directory (containing an index file)
+    #[arg(short, long, global = true)]
+    path: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    #[command(flatten)]
+    Cache(CacheCommand),
+    /// List the caches of the Chromium-based browsers and Electron apps under a home directory or
+    /// mounted filesystem (the current user's home directory by default)
+    Discover { root: Option<PathBuf> },
+}
+
+/// The commands that read the cache at `--path`.
+#[derive(Subcommand, Debug)]
+enum CacheCommand {
+    /// List the entries in the cache
+    Ls,
+    /// Display the index header and the fill level of every block file
+    Info {
+        /// Dump every field of the raw headers
+        #[arg(long)]
+        raw: bool,
+    },
+    /// Write a stream of the entry with the given key to stdout
+    Cat {
+        key: String,
+        /// The stream to write, typically 0 for the response headers and 1 for the body
+        #[arg(short, long, default_value_t = 1)]
+        stream: usize,
+        /// Write the whole response as raw HTTP/1.1, i.e. the status line and headers followed
+        /// by the body
+        #[arg(long, conflicts_with = "stream")]
+        http: bool,
+        /// Undo the body's Content-Encoding and drop the header
+        #[arg(long, requires = "http")]
+        decode: bool,
+        /// Set Content-Length to the length of the written body
+        #[arg(long, requires = "http")]
+        fix_content_length: bool,
+    },
+    /// Write the body of every entry into a directory tree mirroring their URLs
+    Extract {
+        /// The directory to write the bodies into
+        out: PathBuf,
+        /// Keep the bodies' Content-Encoding rather than decoding them
+        #[arg(long)]
+        raw: bool,
+        /// Write every stream of every entry as stored, named by entry address and stream index
+        #[arg(long, conflicts_with = "raw")]
+        streams: bool,
+    },
+    /// Export every entry in another format
+    Export {
+        #[arg(value_enum)]
+        format: ExportFormat,
+        /// The file to write to, instead of stdout
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+    /// Check the block files and entries for structural problems
+    Check,
+    /// List the cookies set by the cached responses
+    Cookies,
+    /// Search the keys, response headers and decoded bodies of every entry for a regex, listing
+    /// each match's address, stream, offset, where it was found, key and matched text
+    Search {
+        pattern: String,
+        /// Take the pattern as hex bytes to match literally, e.g. `89504e47`
+        #[arg(long)]
+        hex: bool,
+        /// Match letters regardless of case
+        #[arg(short, long)]
+        ignore_case: bool,
+        /// Only search the keys (combinable with the other scopes; all are searched by default)
+        #[arg(long)]
+        keys: bool,
+        /// Only search the response headers
+        #[arg(long)]
+        headers: bool,
+        /// Only search the decoded bodies
+        #[arg(long)]
+        bodies: bool,
+    },
+    /// Scan the decoded body of every entry with the YARA-style rules in a file, writing each
+    /// match as JSON Lines
+    Scan { rules: PathBuf },
+    /// Write the certificate chain of the entry with the given key to stdout as PEM
+    Certs { key: String },
+    /// Look up an entry by key and display it
+    Lookup {
+        key: String,
+        /// Dump every field of the raw entry and rankings node
+        #[arg(long)]
+        raw: bool,
+        /// A request header, as `Name: value`, to check against the headers the response varies on
+        #[arg(short = 'H', long = "header")]
+        headers: Vec<String>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ExportFormat {
+    /// The metadata of every entry as JSON Lines
+    Jsonl,
+    /// The cached responses as an HTTP Archive (HAR 1.2)
+    Har,
+    /// The cached responses as WARC 1.1 response and metadata records
+    Warc,
+    /// The cached responses as gzipped WARC 1.1 records
+    WarcGz,
+    /// The times recorded for each entry as a mactime bodyfile
+    Bodyfile,
+    /// The times recorded for each entry as log2timeline CSV
+    L2tCsv,
+}
+
+enum Failure {
+    Error(CCPError),
+    NotFound(String),
+    Problems(usize),
+}
+
+impl From<CCPError> for Failure {
+    fn from(err: CCPError) -> Self {
+        Failure::Error(err)
+    }
+}
+
+impl From<io::Error> for Failure {
+    fn from(err: io::Error) -> Self {
+        Failure::Error(err.into())
+    }
+}
+
+type CliResult = Result<(), Failure>;
+
+/// The first HTTP cache in the block file format under the home directory.
+fn default_cache_path() -> Option<PathBuf> {
+    discover::discover_caches(&dirs::home_dir()?)
+        .into_iter()
+        .find(|cache| cache.kind == CacheKind::Http && cache.format == CacheFormat::BlockFile)
+        .map(|cache| cache.path)
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Failure::Error(err)) => {
+            eprintln!("error: {}", err);
+            ExitCode::from(EXIT_ERROR)
+        }
+        Err(Failure::NotFound(key)) => {
+            eprintln!("no entry with key {:?}", key);
+            ExitCode::from(EXIT_NOT_FOUND)
+        }
+        Err(Failure::Problems(count)) => {
+            eprintln!("found {} problem(s)", count);
+            ExitCode::from(EXIT_PROBLEMS)
+        }
+    }
+}
+
+fn run(args: Args) -> CliResult {
+    match args.command {
+        Command::Discover { root } => discover(root),
+        Command::Cache(command) => run_with_cache(&open_cache(args.path)?, command),
+    }
+}
+
+/// Open the cache at `--path`, or the first HTTP cache under the home directory without one.
+fn open_cache(path: Option<PathBuf>) -> Result<ChromeCache, CCPError> {
+    let path = path
+        .or_else(default_cache_path)
+        .ok_or(CCPError::CacheLocationCouldNotBeDetermined())?;
+    ChromeCache::from_path(path)
+}
+
+fn run_with_cache(cache: &ChromeCache, command: CacheCommand) -> CliResult {
+    // Version 3.0 entry records don't keep the key, which every command but `info` needs.
+    if !matches!(command, CacheCommand::Info { .. }) && cache.version()? == CacheVersion::Version3_0
+    {
+        return Err(CCPError::UnsupportedVersion(
+            "version 3.0 caches are only supported by `ccp info`".to_string(),
+        )
+        .into());
+    }
+
+    match command {
+        CacheCommand::Ls => ls(cache),
+        CacheCommand::Info { raw } => info(cache, raw),
+        CacheCommand::Cat {
+            key,
+            stream,
+            http,
+            decode,
+            fix_content_length,
+        } => {
+            if http {
+                cat_http(
+                    cache,
+                    &key,
+                    &HttpResponseOptions {
+                        decode,
+                        fix_content_length,
+                    },
+                )
+            } else {
+                cat(cache, &key, stream)
+            }
+        }
+        CacheCommand::Extract { out, raw, streams } => {
+            if streams {
+                extract_streams(cache, &out)
+            } else {
+                extract(cache, &out, raw)
+            }
+        }
+        CacheCommand::Export { format, out } => export(cache, format, out.as_deref()),
+        CacheCommand::Check => check(cache),
+        CacheCommand::Cookies => cookies(cache),
+        CacheCommand::Search {
+            pattern,
+            hex,
+            ignore_case,
+            keys,
+            headers,
+            bodies,
+        } => {
+            let all = !(keys || headers || bodies);
+            let options = SearchOptions {
+                keys: all || keys,
+                headers: all || headers,
+                bodies: all || bodies,
+            };
+            search(cache, &pattern, hex, ignore_case, &options)
+        }
+        CacheCommand::Scan { rules } => scan(cache, &rules),
+        CacheCommand::Certs { key } => certs(cache, &key),
+        CacheCommand::Lookup { key, raw, headers } => lookup(cache, &key, raw, &headers),
+    }
+}
+
+fn find(cache: &ChromeCache, key: &str) -> Result<LazyBlockFileCacheEntry, Failure> {
+    cache
+        .lookup(key)?
+        .ok_or_else(|| Failure::NotFound(key.to_string()))
+}
+
+fn format_time(time: chrome_cache_parser::time::WindowsEpochMicroseconds) -> String {
+    time.into_datetime_utc()
+        .map(|time| time.to_rfc3339())
+        .unwrap_or_else(|_| "-".to_string())
+}
+
+fn ls(cache: &ChromeCache) -> CliResult {
+    let mut out = io::stdout().lock();
+
+    for entry in cache.entries()? {
+        let addr = entry.location().addr;
+        match entry.get().and_then(|e| Ok((e, entry.key()?))) {
+            Ok((e, key)) => writeln!(
+                out,
+                "{:08x}\t{:08x}\t{:?}\t{}\t{}",
+                addr.value,
+                e.hash,
+                e.state.kind(),
+                format_time(e.creation_time),
+                key
+            )?,
+            Err(err) => eprintln!("{:08x}: {}", addr.value, err),
+        }
+    }
+
+    Ok(())
+}
+
+fn info(cache: &ChromeCache, raw: bool) -> CliResult {
+    let version = cache.version()?;
+    println!("version\t{:?}", version);
+
+    if version == CacheVersion::Version3_0 {
+        let index = cache.index_v3()?;
+        let header = index.header()?;
+        println!("entries\t{}", header.num_entries);
+        println!("evicted entries\t{}", header.num_evicted_entries);
+        println!("bytes\t{}", header.num_bytes);
+        println!("table length\t{}", header.table_len);
+        println!("created\t{}", format_time(header.create_time));
+    } else {
+        let header = cache.header()?;
+        if raw {
+            print!("{}", header.describe(0));
+        } else {
+            println!("entries\t{}", header.num_entries);
+            println!("bytes\t{}", header.num_bytes);
+            println!("table length\t{}", header.table_len);
+            println!("created\t{}", format_time(header.create_time));
+        }
+    }
+
+    println!();
+    for file in cache.block_files()? {
+        let header = file.header()?;
+        if raw {
+            print!("{}", header.describe(0));
+            continue;
+        }
+
+        let used = header.blocks().filter(|block| block.used).count();
+        let next_file = match header.next_file {
+            0 => "-".to_string(),
+            next_file => format!("data_{}", next_file),
+        };
+        println!(
+            "data_{}\tentry size {}\t{} of {} entries\t{} blocks used\tnext {}",
+            header.this_file,
+            header.entry_size,
+            header.num_entries,
+            header.max_entries,
+            used,
+            next_file
+        );
+    }
+
+    Ok(())
+}
+
+fn cat(cache: &ChromeCache, key: &str, stream: usize) -> CliResult {
+    let entry = find(cache, key)?;
+    let mut reader = entry.stream_reader(stream)?;
+    io::copy(&mut reader, &mut io::stdout().lock())?;
+    Ok(())
+}
+
+fn cat_http(cache: &ChromeCache, key: &str, options: &HttpResponseOptions) -> CliResult {
+    let entry = find(cache, key)?;
+    entry.write_http_response(io::stdout().lock(), options)?;
+    Ok(())
+}
+
+fn cookies(cache: &ChromeCache) -> CliResult {
+    let mut out = io::stdout().lock();
+
+    for cached in cookies::cached_cookies(cache)? {
+        let cookie = &cached.cookie;
+        let mut flags = Vec::new();
+        if cookie.secure {
+            flags.push("Secure".to_string());
+        }
+        if cookie.http_only {
+            flags.push("HttpOnly".to_string());
+        }
+        if let Some(same_site) = cookie.same_site {
+            flags.push(format!("SameSite={:?}", same_site));
+        }
+
+        writeln!(
+            out,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            format_time(cached.response_time),
+            cached.domain,
+            cached.path,
+            cookie.name,
+            cookie.value,
+            cached
+                .expires
+                .map_or("session".to_string(), |expires| expires.to_rfc3339()),
+            flags.join(","),
+            cached.url
+        )?;
+    }
+
+    Ok(())
+}
+
+/// The number of bytes of each match shown by `search`.
+const SEARCH_SNIPPET_LEN: usize = 80;
+
+fn parse_hex(hex: &str) -> Result<Vec<u8>, CCPError> {
+    let digits = hex
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect::<Vec<_>>();
+    let nibbles = digits
+        .iter()
+        .map(|digit| digit.to_digit(16).map(|nibble| nibble as u8))
+        .collect::<Option<Vec<_>>>();
+    match nibbles {
+        Some(nibbles) if !nibbles.is_empty() && nibbles.len() % 2 == 0 => Ok(nibbles
+            .chunks(2)
+            .map(|pair| pair[0] << 4 | pair[1])
+            .collect()),
+        _ => Err(CCPError::InvalidData(format!(
+            "invalid hex pattern {:?}",
+            hex
+        ))),
+    }
+}
+
+fn search(
+    cache: &ChromeCache,
+    pattern: &str,
+    hex: bool,
+    ignore_case: bool,
+    options: &SearchOptions,
+) -> CliResult {
+    let pattern = if hex {
+        search::literal_pattern(&parse_hex(pattern)?)?
+    } else {
+        regex::bytes::RegexBuilder::new(pattern)
+            .case_insensitive(ignore_case)
+            .build()
+            .map_err(CCPError::from)?
+    };
+    let mut out = io::stdout().lock();
+
+    search::search(cache, &pattern, options, |found| {
+        let snippet =
+            String::from_utf8_lossy(&found.matched[..found.matched.len().min(SEARCH_SNIPPET_LEN)])
+                .escape_debug()
+                .to_string();
+        writeln!(
+            out,
+            "{:08x}\t{}\t{}\t{:?}\t{}\t{}",
+            found.address.value,
+            found
+                .stream
+                .map_or("-".to_string(), |stream| stream.to_string()),
+            found.offset,
+            found.target,
+            found.key,
+            snippet
+        )?;
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+fn scan(cache: &ChromeCache, path: &Path) -> CliResult {
+    let rules = rules::parse_rules(&fs::read_to_string(path)?)?;
+    let mut out = io::stdout().lock();
+
+    let found = rules::scan(cache, &rules, |found| {
+        serde_json::to_writer(&mut out, &found).map_err(io::Error::from)?;
+        out.write_all(b"\n")?;
+        Ok(())
+    })?;
+    eprintln!("{} matches of {} rules", found, rules.len());
+    Ok(())
+}
+
+fn discover(root: Option<PathBuf>) -> CliResult {
+    let root = root
+        .or_else(dirs::home_dir)
+        .ok_or(CCPError::CacheLocationCouldNotBeDetermined())?;
+    let mut out = io::stdout().lock();
+
+    for cache in discover::discover_caches(&root) {
+        writeln!(
+            out,
+            "{}\t{}\t{:?}\t{:?}\t{}",
+            cache.application,
+            cache.profile.as_deref().unwrap_or("-"),
+            cache.kind,
+            cache.format,
+            cache.path.display()
+        )?;
+    }
+
+    Ok(())
+}
+
+fn certs(cache: &ChromeCache, key: &str) -> CliResult {
+    let entry = find(cache, key)?;
+    let info = entry.response_info()?;
+    let ssl_info = info.ssl_info.unwrap_or_default();
+
+    if ssl_info.certificates.is_empty() {
+        eprintln!("no certificates recorded for {:?}", key);
+    }
+    io::stdout().write_all(ssl_info.certificates_pem().as_bytes())?;
+    Ok(())
+}
+
+fn extract(cache: &ChromeCache, out: &Path, raw: bool) -> CliResult {
+    let options = export::extract::ExtractOptions { raw };
+    let report = export::extract::extract(cache, out, &options)?;
+
+    for (addr, err) in &report.failures {
+        eprintln!("{:08x}: {}", addr.value, err);
+    }
+    for path in report.changed_sources() {
+        eprintln!("warning: {} changed during extraction", path.display());
+    }
+    eprintln!(
+        "extracted {} files, see {} and {}",
+        report.files.len(),
+        out.join(export::extract::MANIFEST_FILE_NAME).display(),
+        out.join(export::extract::HASH_MANIFEST_FILE_NAME).display()
+    );
+
+    Ok(())
+}
+
+fn extract_streams(cache: &ChromeCache, out: &Path) -> CliResult {
+    fs::create_dir_all(out)?;
+
+    for entry in cache.entries()? {
+        let addr = entry.location().addr;
+        let readers = match entry.stream_readers() {
+            Ok(readers) => readers,
+            Err(err) => {
+                eprintln!("{:08x}: {}", addr.value, err);
+                continue;
+            }
+        };
+
+        for (stream, reader) in readers.into_iter().enumerate() {
+            let written = reader.and_then(|mut reader| {
+                let path = out.join(format!("{:08x}_{}", addr.value, stream));
+                let mut file = fs::File::create(path)?;
+                io::copy(&mut reader, &mut file)?;
+                Ok(())
+            });
+
+            if let Err(err) = written {
+                eprintln!("{:08x} stream {}: {}", addr.value, stream, err);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn export(cache: &ChromeCache, format: ExportFormat, out: Option<&Path>) -> CliResult {
+    let writer: Box<dyn Write> = match out {
+        Some(out) => Box::new(io::BufWriter::new(fs::File::create(out)?)),
+        None => Box::new(io::stdout().lock()),
+    };
+
+    let written = match format {
+        ExportFormat::Jsonl => export::jsonl::write_jsonl(cache, writer)?,
+        ExportFormat::Har => export::har::write_har(cache, writer)?,
+        ExportFormat::Warc | ExportFormat::WarcGz => {
+            let options = export::warc::WarcExportOptions {
+                gzip: matches!(format, ExportFormat::WarcGz),
+            };
+            export::warc::write_warc(cache, writer, &options)?
+        }
+        ExportFormat::Bodyfile => export::timeline::write_bodyfile(cache, writer)?,
+        ExportFormat::L2tCsv => export::timeline::write_l2t_csv(cache, writer)?,
+    };
+    eprintln!("exported {} records", written);
+
+    Ok(())
+}
+
+/// Checks the structure of the cache, reporting every problem found rather than stopping at the
+/// first one.
+struct Checker {
+    block_files: HashMap<u32, LazyBlockFile>,
+    problems: usize,
+}
+
+impl Checker {
+    fn problem(&mut self, context: impl std::fmt::Display, message: impl std::fmt::Display) {
+        println!("{}: {}", context, message);
+        self.problems += 1;
+    }
+
+    /// Check that every block covered by the address is marked as allocated.
+    fn check_allocated(&mut self, context: &str, addr: CacheAddr) {
+        if addr.file_type() == FileType::External {
+            return;
+        }
+
+        let Some(file) = self.block_files.get(&addr.file_number()) else {
+            self.problem(
+                context,
+                format!("{} is not a known block file", addr.file_name()),
+            );
+            return;
+        };
+
+        let unallocated = match file.header() {
+            Ok(header) => (addr.start_block()..addr.start_block() + addr.num_blocks())
+                .filter(|block| !header.allocation_map.is_used(*block as usize))
+                .count(),
+            Err(_) => return,
+        };
+
+        if unallocated > 0 {
+            self.problem(
+                context,
+                format!(
+                    "{} of {} block(s) at {} block {} are not allocated",
+                    unallocated,
+                    addr.num_blocks(),
+                    addr.file_name(),
+                    addr.start_block()
+                ),
+            );
+        }
+    }
+
+    fn check_block_file(&mut self, file: &LazyBlockFile) {
+        let context = file.path().display().to_string();
+        match file.header() {
+            Ok(header) => {
+                let used = header.blocks().filter(|block| block.used).count();
+                if header.updating != 0 {
+                    self.problem(&context, "was being updated when the cache was closed");
+                }
+                if header.num_entries < 0 || header.num_entries as usize > used {
+                    self.problem(
+                        &context,
+                        format!(
+                            "header counts {} entries but only {} blocks are allocated",
+                            header.num_entries, used
+                        ),
+                    );
+                }
+            }
+            Err(err) => self.problem(&context, err),
+        }
+    }
+
+    fn check_entry(&mut self, cache_path: &Path, mut entry: LazyBlockFileCacheEntry) {
+        let context = entry.location().to_string();
+        let addr = entry.location().addr;
+
+        let (key, parsed) = match entry.get().and_then(|e| Ok((entry.key()?, e.clone()))) {
+            Ok(parsed) => parsed,
+            Err(err) => return self.problem(&context, err),
+        };
+
+        self.check_allocated(&context, addr);
+
+        if super_fast_hash(key.as_bytes()) != parsed.hash {
+            self.problem(&context, format!("hash doesn't match key {:?}", key));
+        }
+
+        for (stream, (data_addr, size)) in parsed
+            .data_addr
+            .iter()
+            .zip(parsed.data_size.iter())
+            .enumerate()
+        {
+            if !data_addr.is_initialized() {
+                if *size > 0 {
+                    self.problem(
+                        &context,
+                        format!("stream {} has data but no address", stream),
+                    );
+                }
+                continue;
+            }
+
+            match data_addr.file_type() {
+                FileType::External => {
+                    if !cache_path.join(data_addr.file_name()).exists() {
+                        self.problem(
+                            &context,
+                            format!("stream {} is missing {}", stream, data_addr.file_name()),
+                        );
+                    }
+                }
+                file_type => {
+                    let capacity =
+                        file_type.block_size().unwrap_or(0) * data_addr.num_blocks() as usize;
+                    if *size as usize > capacity {
+                        self.problem(
+                            &context,
+                            format!(
+                                "stream {} holds {} bytes but its blocks fit {}",
+                                stream, size, capacity
+                            ),
+                        );
+                    }
+                    self.check_allocated(&format!("{} stream {}", context, stream), *data_addr);
+                }
+            }
+        }
+
+        match entry.get_rankings_node() {
+            Ok(node) => match node.get() {
+                Ok(rankings) => {
+                    let contents = rankings.contents;
+                    if contents.value != addr.value {
+                        self.problem(
+                            node.location(),
+                            format!("rankings node points to {:08x}", contents.value),
+                        );
+                    }
+                    self.check_allocated(&node.location().to_string(), node.location().addr);
+                }
+                Err(err) => self.problem(node.location(), err),
+            },
+            Err(err) => self.problem(&context, err),
+        }
+    }
+}
+
+fn check(cache: &ChromeCache) -> CliResult {
+    let files = cache.block_files()?;
+    let mut checker = Checker {
+        block_files: HashMap::new(),
+        problems: 0,
+    };
+
+    for file in files {
+        checker.check_block_file(&file);
+        if let Ok(header) = file.header() {
+            checker
+                .block_files
+                .insert(header.this_file as u32, file.clone());
+        }
+    }
+
+    let cache_path = cache.path().to_path_buf();
+    for entry in cache.entries()? {
+        checker.check_entry(&cache_path, entry);
+    }
+
+    match checker.problems {
+        0 => Ok(()),
+        problems => Err(Failure::Problems(problems)),
+    }
+}
+
+fn lookup(cache: &ChromeCache, key: &str, raw: bool, headers: &[String]) -> CliResult {
+    let mut entry = find(cache, key)?;
+
+    if raw {
+        print!("{}", entry.describe()?);
+        print!("{}", entry.get_rankings_node()?.describe()?);
+        return Ok(());
+    }
+
+    let e = entry.get()?.clone();
+    println!("location\t{}", entry.location());
+    println!("key\t{}", entry.key()?);
+    println!("hash\t{:08x}", e.hash);
+    println!("state\t{:?}", e.state.kind());
+    println!("created\t{}", format_time(e.creation_time));
+    println!("reuse count\t{}", e.reuse_count);
+    println!("refetch count\t{}", e.refetch_count);
+
+    for (stream, location) in entry.stream_locations()?.iter().enumerate() {
+        match location {
+            Some(location) => println!(
+                "stream {}\t{} bytes\t{}",
+                stream, e.data_size[stream], location
+            ),
+            None => println!("stream {}\tempty", stream),
+        }
+    }
+
+    if let Ok(types) = entry.content_types() {
+        let sniffed = types.sniffed.map_or("-", |kind| kind.mime_type());
+        println!(
+            "content type\t{}\tsniffed {}{}",
+            types.declared.as_deref().unwrap_or("-"),
+            sniffed,
+            if types.is_mismatch() {
+                "\t(mismatch)"
+            } else {
+                ""
+            }
+        );
+    }
+
+    if let Ok(freshness) = entry.freshness_at(chrono::Utc::now()) {
+        let lifetime = if freshness.lifetime == chrono::TimeDelta::max_value() {
+            "forever".to_string()
+        } else {
+            format!("{}s", freshness.lifetime.num_seconds())
+        };
+        println!(
+            "freshness\t{:?}\tlifetime {}\tage {}s",
+            freshness.status,
+            lifetime,
+            freshness.current_age.num_seconds()
+        );
+    }
+
+    if let Ok(info) = entry.response_info() {
+        if let Some(vary_data) = &info.vary_data {
+            let request_headers = headers
+                .iter()
+                .filter_map(|header| header.split_once(':'))
+                .map(|(name, value)| (name.trim(), value.trim()))
+                .collect::<Vec<_>>();
+            let matches = match (headers.is_empty(), info.matches_request(&request_headers)) {
+                (true, _) => "",
+                (false, true) => "\t(matches request)",
+                (false, false) => "\t(mismatch)",
+            };
+            println!(
+                "vary\t{}\tdigest {}{}",
+                chrome_cache_parser::vary::vary_header_names(&info.headers).join(","),
+                vary_data.to_hex(),
+                matches
+            );
+        }
+        if let Some(endpoint) = &info.remote_endpoint {
+            println!(
+                "remote endpoint\t{}\tprotocol {}\talpn {}",
+                endpoint,
+                info.connection_protocol().unwrap_or("-"),
+                info.alpn_negotiated_protocol.as_deref().unwrap_or("-")
+            );
+        }
+    }
+
+    if let Some(ssl_info) = entry.response_info().ok().and_then(|info| info.ssl_info) {
+        println!(
+            "tls\t{}\tcipher suite {}\tgroup {}\tsignature {}",
+            ssl_info.tls_version().unwrap_or("-"),
+            ssl_info
+                .cipher_suite()
+                .map_or("-".to_string(), |suite| format!("0x{:04x}", suite)),
+            ssl_info.key_exchange_group_name().unwrap_or("-"),
+            ssl_info.peer_signature_algorithm_name().unwrap_or("-"),
+        );
+        let status = ssl_info.cert_status_names();
+        println!(
+            "certificates\t{}\tstatus {}",
+            ssl_info.certificates.len(),
+            if status.is_empty() {
+                "-".to_string()
+            } else {
+                status.join(",")
+            }
+        );
+    }
+
+    let rankings = entry.get_rankings_node()?;
+    let node = rankings.get()?;
+    println!("last used\t{}", format_time(node.last_used));
+    println!("last modified\t{}", format_time(node.last_modified));
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_hex() {
+    assert_eq!(parse_hex("89 50 4e 47").unwrap(), b"\x89PNG");
+    assert_eq!(parse_hex("fF00").unwrap(), [0xff, 0x00]);
+    for hex in ["", "f", "+f", "-1", "0x", "zz"] {
+        assert!(parse_hex(hex).is_err(), "{:?}", hex);
+    }
+}
@@ -0,0 +1,494 @@
+//! Serve the responses of a Chrome cache over HTTP, to browse a cache offline.
+//!
+//! Requests are mapped to cache entries by URL, given either as an absolute request URL (with the
+//! server configured as the browser's HTTP proxy), as the path (`/https://example.com/`) or by the
+//! `Host` header. In path mode, redirects and root-relative links (`/style.css`) are resolved
+//! against the URL of the page they're on.
+use clap::Parser;
+use std::{
+    collections::HashMap,
+    io::{Cursor, Read},
+    path::PathBuf,
+    process::ExitCode,
+};
+use tiny_http::{Header, Request, Response, Server, StatusCode};
+
+use chrome_cache_parser::{CCPError, CCPResult, CacheKey, CacheVersion, ChromeCache};
+
+/// The number of near matches listed when no entry matches a request.
+const NEAR_MATCHES: usize = 20;
+
+/// Serve the responses of a Chrome cache directory over HTTP.
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Args {
+    /// Path to the cache directory (containing an index file)
+    #[arg(short, long)]
+    path: PathBuf,
+
+    /// The address to listen on
+    #[arg(short, long, default_value = "127.0.0.1:8080")]
+    bind: String,
+}
+
+/// The cached responses, by URL.
+struct Replay {
+    cache: ChromeCache,
+    /// The keys of the entries for each URL. A URL has several entries when the cache is
+    /// partitioned by top-frame site.
+    keys: HashMap<String, Vec<String>>,
+}
+
+impl Replay {
+    fn new(cache: ChromeCache) -> CCPResult<Replay> {
+        // Version 3.0 entry records don't keep the key, so their URLs are unknown.
+        if cache.version()? == CacheVersion::Version3_0 {
+            return Err(CCPError::UnsupportedVersion(
+                "version 3.0 caches can't be replayed".to_string(),
+            ));
+        }
+
+        let mut keys: HashMap<String, Vec<String>> = HashMap::new();
+        for entry in cache.entries()? {
+            if let Ok(key) = entry.key() {
+                keys.entry(CacheKey::parse(&key).url).or_default().push(key);
+            }
+        }
+
+        Ok(Replay { cache, keys })
+    }
+
+    /// The response for a request for one of the given URLs, the first that's cached.
+    fn response(
+        &self,
+        urls: &[String],
+        path_mode: bool,
+    ) -> CCPResult<Option<Response<Cursor<Vec<u8>>>>> {
+        let Some((url, key)) = urls.iter().find_map(|url| {
+            self.keys
+                .get(url)
+                .and_then(|keys| keys.first())
+                .map(|key| (url, key))
+        }) else {
+            return Ok(None);
+        };
+        let Some(entry) = self.cache.lookup(key)? else {
+            return Ok(None);
+        };
+
+        let info = entry.response_info()?;
+        let mut body = Vec::new();
+        entry.stream_reader(1)?.read_to_end(&mut body)?;
+
+        // The body is served as stored, so the `Content-Encoding` header still applies, but its
+        // length is recomputed.
+        let mut headers = Vec::new();
+        for (name, value) in &info.headers.headers {
+            if name.eq_ignore_ascii_case("content-length") {
+                continue;
+            }
+            // Keep redirects within the replay when URLs are given as paths.
+            let location = (path_mode && name.eq_ignore_ascii_case("location"))
+                .then(|| resolve(url, value))
+                .filter(|location| is_absolute(location));
+            let value = match location {
+                Some(location) => format!("/{}", location),
+                None => value.clone(),
+            };
+            if let Ok(header) = Header::from_bytes(name.as_bytes(), value.as_bytes()) {
+                headers.push(header);
+            }
+        }
+
+        let status = info.headers.status_code().unwrap_or(200);
+        let length = body.len();
+        Ok(Some(Response::new(
+            StatusCode(status),
+            headers,
+            Cursor::new(body),
+            Some(length),
+            None,
+        )))
+    }
+
+    /// A 404 page listing the cached URLs closest to the requested one.
+    fn not_found(&self, url: &str) -> Response<Cursor<Vec<u8>>> {
+        let mut page = format!(
+            "<!doctype html>\n<title>Not cached</title>\n<h1>Not cached</h1>\n<p>{}</p>\n",
+            escape_html(url)
+        );
+        let near_matches = near_matches(self.keys.keys(), url);
+        if !near_matches.is_empty() {
+            page.push_str("<h2>Near matches</h2>\n<ul>\n");
+            for cached in near_matches {
+                page.push_str(&format!(
+                    "<li><a href=\"/{}\">{}</a></li>\n",
+                    escape_html(cached),
+                    escape_html(cached)
+                ));
+            }
+            page.push_str("</ul>\n");
+        }
+
+        let length = page.len();
+        Response::new(
+            StatusCode(404),
+            vec![
+                Header::from_bytes(&b"Content-Type"[..], &b"text/html; charset=utf-8"[..]).unwrap(),
+            ],
+            Cursor::new(page.into_bytes()),
+            Some(length),
+            None,
+        )
+    }
+}
+
+/// The cached URLs most similar to `url`: those on the same host, or failing that any, with the
+/// longest common prefix.
+fn near_matches<'a>(cached: impl Iterator<Item = &'a String>, url: &str) -> Vec<&'a str> {
+    let host = CacheKey::parse(url).host().map(str::to_string);
+    let cached = cached.collect::<Vec<_>>();
+    let same_host = cached
+        .iter()
+        .copied()
+        .filter(|cached| host.is_some() && CacheKey::parse(cached).host() == host.as_deref())
+        .collect::<Vec<_>>();
+    let candidates = if same_host.is_empty() {
+        cached
+    } else {
+        same_host
+    };
+
+    let mut ranked = candidates
+        .into_iter()
+        .map(|cached| (common_prefix_len(cached, url), cached.as_str()))
+        .collect::<Vec<_>>();
+    ranked.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(b.1)));
+    ranked
+        .into_iter()
+        .take(NEAR_MATCHES)
+        .map(|(_, cached)| cached)
+        .collect()
+}
+
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.bytes().zip(b.bytes()).take_while(|(a, b)| a == b).count()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Whether a URL is an absolute `http://` or `https://` URL.
+fn is_absolute(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// The scheme and authority of an absolute URL, e.g. `https://example.com` for
+/// `https://example.com/a?b`.
+fn origin(url: &str) -> Option<&str> {
+    let authority = url.find("://")? + 3;
+    let end = url[authority..]
+        .find(['/', '?', '#'])
+        .map_or(url.len(), |end| authority + end);
+    Some(&url[..end])
+}
+
+/// Whether a reference starts with a scheme, e.g. `https:` or `data:`, rather than being relative.
+fn has_scheme(reference: &str) -> bool {
+    reference.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+/// Resolve a reference, e.g. a `Location` header or a link, against the absolute URL of the
+/// response it's in.
+///
+/// See: https://www.rfc-editor.org/rfc/rfc3986#section-5.2
+fn resolve(base: &str, reference: &str) -> String {
+    let Some(origin) = origin(base).filter(|_| !has_scheme(reference)) else {
+        return reference.to_string();
+    };
+    if let Some(rest) = reference.strip_prefix("//") {
+        let scheme = &origin[..origin.find("://").unwrap_or(0)];
+        return format!("{}://{}", scheme, rest);
+    }
+
+    let base = base.split('#').next().unwrap_or(base);
+    let base_path = base[origin.len()..].split('?').next().unwrap_or("");
+    if reference.is_empty() || reference.starts_with('#') {
+        return format!("{}{}", base, reference);
+    }
+    if reference.starts_with('?') {
+        return format!("{}{}{}", origin, base_path, reference);
+    }
+
+    let (path, suffix) = reference.split_at(reference.find(['?', '#']).unwrap_or(reference.len()));
+    let path = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        let directory = base_path.rfind('/').map_or("/", |end| &base_path[..=end]);
+        format!("{}{}", directory, path)
+    };
+
+    // Remove the `.` and `..` segments.
+    let mut segments = Vec::new();
+    for segment in path.split('/').skip(1) {
+        match segment {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    if path.ends_with("/.") || path.ends_with("/..") {
+        segments.push("");
+    }
+    format!("{}/{}{}", origin, segments.join("/"), suffix)
+}
+
+/// The URL of the page replayed at `referer`, when it was requested by path, e.g.
+/// `https://example.com/` for `http://127.0.0.1:8080/https://example.com/`.
+fn replayed_url(referer: &str) -> Option<&str> {
+    let url = referer[origin(referer)?.len()..].strip_prefix('/')?;
+    is_absolute(url).then_some(url)
+}
+
+/// A URL along with the same URL over the other scheme, as browsers only send plain `http://`
+/// URLs to HTTP proxies.
+fn with_other_scheme(url: &str) -> Vec<String> {
+    let other = match (url.strip_prefix("https://"), url.strip_prefix("http://")) {
+        (Some(rest), _) => format!("http://{}", rest),
+        (_, Some(rest)) => format!("https://{}", rest),
+        _ => return vec![url.to_string()],
+    };
+    vec![url.to_string(), other]
+}
+
+/// The URLs a request for `url` with the given `Host` and `Referer` headers may be for, in order
+/// of preference, and whether the URL was given as the path.
+fn request_urls(url: &str, host: Option<&str>, referer: Option<&str>) -> (Vec<String>, bool) {
+    if is_absolute(url) {
+        return (with_other_scheme(url), false);
+    }
+    if let Some(url) = url.strip_prefix('/').filter(|url| is_absolute(url)) {
+        return (with_other_scheme(url), true);
+    }
+    // A root-relative link on a page requested by path, e.g. `/style.css` on
+    // `/https://example.com/`, is relative to the page's URL rather than the replay's.
+    if let Some(page) = referer.and_then(replayed_url) {
+        return (with_other_scheme(&resolve(page, url)), true);
+    }
+
+    match host {
+        Some(host) => (
+            vec![
+                format!("https://{}{}", host, url),
+                format!("http://{}{}", host, url),
+            ],
+            false,
+        ),
+        None => (Vec::new(), false),
+    }
+}
+
+fn header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv(name))
+        .map(|header| header.value.as_str())
+}
+
+fn serve(replay: &Replay, server: &Server) {
+    for request in server.incoming_requests() {
+        let (urls, path_mode) = request_urls(
+            request.url(),
+            header(&request, "Host"),
+            header(&request, "Referer"),
+        );
+        let response = match replay.response(&urls, path_mode) {
+            Ok(Some(response)) => response,
+            Ok(None) => replay.not_found(urls.first().map_or(request.url(), String::as_str)),
+            Err(err) => {
+                eprintln!("error: {}: {}", request.url(), err);
+                Response::from_data(err.to_string().into_bytes()).with_status_code(500)
+            }
+        };
+
+        eprintln!(
+            "{} {} {}",
+            response.status_code().0,
+            request.method(),
+            request.url()
+        );
+        if let Err(err) = request.respond(response) {
+            eprintln!("error: {}", err);
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let replay = match ChromeCache::from_path(args.path).and_then(Replay::new) {
+        Ok(replay) => replay,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let server = match Server::http(&args.bind) {
+        Ok(server) => server,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    eprintln!(
+        "serving {} cached urls on http://{}/",
+        replay.keys.len(),
+        args.bind
+    );
+    serve(&replay, &server);
+    ExitCode::SUCCESS
+}
+
+#[test]
+fn test_resolve() {
+    let base = "https://example.com/a/b/page?q=1#top";
+    assert_eq!(resolve(base, "https://other.org/"), "https://other.org/");
+    assert_eq!(
+        resolve(base, "//cdn.example.com/x.js"),
+        "https://cdn.example.com/x.js"
+    );
+    assert_eq!(resolve(base, "/style.css"), "https://example.com/style.css");
+    assert_eq!(resolve(base, "img.png"), "https://example.com/a/b/img.png");
+    assert_eq!(
+        resolve(base, "../c/./d?e#f"),
+        "https://example.com/a/c/d?e#f"
+    );
+    assert_eq!(resolve(base, ".."), "https://example.com/a/");
+    assert_eq!(resolve(base, "?q=2"), "https://example.com/a/b/page?q=2");
+    assert_eq!(
+        resolve(base, "#end"),
+        "https://example.com/a/b/page?q=1#end"
+    );
+    assert_eq!(
+        resolve(base, "/go?to=https://other.org/"),
+        "https://example.com/go?to=https://other.org/"
+    );
+    assert_eq!(
+        resolve("https://example.com", "new"),
+        "https://example.com/new"
+    );
+}
+
+#[test]
+fn test_with_other_scheme() {
+    assert_eq!(
+        with_other_scheme("https://example.com/"),
+        ["https://example.com/", "http://example.com/"]
+    );
+    assert_eq!(
+        with_other_scheme("http://example.com/"),
+        ["http://example.com/", "https://example.com/"]
+    );
+    assert_eq!(
+        with_other_scheme("ftp://example.com/"),
+        ["ftp://example.com/"]
+    );
+}
+
+#[test]
+fn test_request_urls() {
+    let replay = Some("http://127.0.0.1:8080/https://example.com/a/page");
+
+    // As a proxy.
+    assert_eq!(
+        request_urls("http://example.com/", Some("example.com"), None),
+        (
+            vec![
+                "http://example.com/".to_string(),
+                "https://example.com/".to_string()
+            ],
+            false
+        )
+    );
+    // By path.
+    assert_eq!(
+        request_urls("/https://example.com/a/page", Some("127.0.0.1:8080"), None),
+        (
+            vec![
+                "https://example.com/a/page".to_string(),
+                "http://example.com/a/page".to_string()
+            ],
+            true
+        )
+    );
+    // A root-relative link on a page requested by path.
+    assert_eq!(
+        request_urls("/style.css", Some("127.0.0.1:8080"), replay),
+        (
+            vec![
+                "https://example.com/style.css".to_string(),
+                "http://example.com/style.css".to_string()
+            ],
+            true
+        )
+    );
+    // By host, as when the site's name resolves to the server.
+    assert_eq!(
+        request_urls(
+            "/style.css",
+            Some("example.com"),
+            Some("https://example.com/")
+        ),
+        (
+            vec![
+                "https://example.com/style.css".to_string(),
+                "http://example.com/style.css".to_string()
+            ],
+            false
+        )
+    );
+    assert_eq!(request_urls("/style.css", None, None), (Vec::new(), false));
+}
+
+#[test]
+fn test_near_matches() {
+    let cached = [
+        "https://example.com/a/1",
+        "https://example.com/a/2",
+        "https://example.com/b",
+        "https://example.org/a/1",
+    ]
+    .map(String::from);
+
+    assert_eq!(
+        near_matches(cached.iter(), "https://example.com/a/3"),
+        [
+            "https://example.com/a/1",
+            "https://example.com/a/2",
+            "https://example.com/b"
+        ]
+    );
+    assert_eq!(
+        near_matches(cached.iter(), "https://example.net/a/1"),
+        [
+            "https://example.com/a/1",
+            "https://example.com/a/2",
+            "https://example.com/b",
+            "https://example.org/a/1"
+        ]
+    );
+}
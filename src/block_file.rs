@@ -1,37 +1,85 @@
 use std::{
     cell::RefCell,
     cmp::min,
-    collections::{hash_map::Entry, HashMap},
+    collections::{hash_map::Entry, HashMap, HashSet},
     fmt,
     fs::{self, File},
-    io::{self, BufReader, Read},
+    io::{self, BufReader, Read, Write},
     mem,
-    path::PathBuf,
+    path::{Path, PathBuf},
     rc::Rc,
 };
 
-use zerocopy::{FromBytes, FromZeroes};
+use chrono::{DateTime, Utc};
+use zerocopy::{AsBytes, FromBytes, FromZeroes};
 
 use crate::{
     cache_address::{CacheAddr, FileType},
+    cache_index_v3::{EntryGroup, IndexCell},
+    content_encoding,
+    describe::{Describe, FieldWriter, StructDescription},
     error::{self, CCPResult},
+    freshness::Freshness,
+    http_response::{response_head, HttpResponseOptions},
+    response_info::{HttpResponseInfo, RemoteEndpoint},
+    sniff::{ContentTypes, SNIFF_LEN},
+    source_location::SourceLocation,
     time::WindowsEpochMicroseconds,
     CCPError,
 };
 use static_assertions as sa;
 
 const BLOCK_MAGIC: u32 = 0xc104cac3;
-const BLOCK_HEADER_SIZE: usize = 8192;
+pub(crate) const BLOCK_HEADER_SIZE: usize = 8192;
 const MAX_BLOCKS: usize = (BLOCK_HEADER_SIZE - 80) * 8;
 const INLINE_KEY_SIZE: usize = 160;
-
-#[derive(Debug, FromZeroes, FromBytes)]
+const INLINE_KEY_OFFSET: usize = mem::size_of::<BlockFileCacheEntry>() - INLINE_KEY_SIZE;
+
+/// The block types that are stored in block files, each starting with its own `data_N` file.
+pub const BLOCK_FILE_TYPES: [FileType; 4] = [
+    FileType::Rankings,
+    FileType::Block256,
+    FileType::Block1k,
+    FileType::Block4k,
+];
+
+/// The block types stored in block files by version 3.0 caches, which keep entry records instead
+/// of entries and rankings nodes.
+pub const BLOCK_FILE_TYPES_V3: [FileType; 5] = [
+    FileType::Block256,
+    FileType::Block1k,
+    FileType::Block4k,
+    FileType::BlockEntries,
+    FileType::BlockEvicted,
+];
+
+/// The allocation bitmap of a block file. Each bit represents a block in the file, set when the
+/// block is in use.
+#[derive(Debug, FromZeroes, FromBytes, AsBytes)]
 #[repr(C)]
-struct AllocBitmap {
+pub struct AllocBitmap {
     data: [u32; MAX_BLOCKS / 32],
 }
 
-#[derive(Debug, FromZeroes, FromBytes, Clone)]
+impl AllocBitmap {
+    /// Whether the given block is marked as allocated. Blocks beyond the bitmap are never used.
+    pub fn is_used(&self, block: usize) -> bool {
+        match self.data.get(block / 32) {
+            Some(word) => word & (1 << (block % 32)) != 0,
+            None => false,
+        }
+    }
+}
+
+/// The allocation state of a single block within a block file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockAllocation {
+    pub block: usize,
+    pub used: bool,
+}
+
+#[derive(Debug, FromZeroes, FromBytes, AsBytes, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[repr(C, packed(4))]
 pub struct RankingsNode {
     pub last_used: WindowsEpochMicroseconds,
@@ -45,10 +93,24 @@ pub struct RankingsNode {
 
 sa::const_assert_eq!(mem::size_of::<RankingsNode>(), 36);
 
+impl Describe for RankingsNode {
+    fn describe(&self, offset: usize) -> StructDescription {
+        FieldWriter::new(self.as_bytes(), offset)
+            .time("last_used", self.last_used)
+            .time("last_modified", self.last_modified)
+            .addr("next", self.next)
+            .addr("prev", self.prev)
+            .addr("contents", self.contents)
+            .int("dirty", self.dirty)
+            .hex("self_hash", self.self_hash)
+            .finish("RankingsNode")
+    }
+}
+
 // See: https://chromium.googlesource.com/chromium/src/net/+/ddbc6c5954c4bee29902082eb9052405e83abc02/disk_cache/disk_format_base.h
-#[derive(Debug, FromZeroes, FromBytes)]
+#[derive(Debug, FromZeroes, FromBytes, AsBytes)]
 #[repr(C)]
-struct BlockFileHeader {
+pub struct BlockFileHeader {
     pub magic: u32,
     pub version: u32,
     pub this_file: i16,
@@ -63,7 +125,44 @@ struct BlockFileHeader {
     pub allocation_map: AllocBitmap,
 }
 
-#[derive(FromZeroes, FromBytes, Clone)]
+impl Describe for BlockFileHeader {
+    fn describe(&self, offset: usize) -> StructDescription {
+        let used = self.blocks().filter(|block| block.used).count();
+
+        FieldWriter::new(self.as_bytes(), offset)
+            .hex("magic", self.magic)
+            .hex("version", self.version)
+            .int("this_file", self.this_file)
+            .int("next_file", self.next_file)
+            .int("entry_size", self.entry_size)
+            .int("num_entries", self.num_entries)
+            .int("max_entries", self.max_entries)
+            .ints("empty", &self.empty)
+            .ints("hints", &self.hints)
+            .int("updating", self.updating)
+            .ints("user", &self.user)
+            .field(
+                "allocation_map",
+                mem::size_of::<AllocBitmap>(),
+                format!("{} of {} blocks used", used, self.max_entries),
+            )
+            .finish("BlockFileHeader")
+    }
+}
+
+impl BlockFileHeader {
+    /// Iterate over the allocation state of every block the file can hold.
+    pub fn blocks(&self) -> impl Iterator<Item = BlockAllocation> + '_ {
+        let max_blocks = min(self.max_entries.max(0) as usize, MAX_BLOCKS);
+        (0..max_blocks).map(|block| BlockAllocation {
+            block,
+            used: self.allocation_map.is_used(block),
+        })
+    }
+}
+
+#[derive(FromZeroes, FromBytes, AsBytes, Clone)]
+#[repr(C)]
 pub struct InlineCacheKey {
     key: [u8; INLINE_KEY_SIZE],
 }
@@ -75,6 +174,13 @@ impl fmt::Debug for InlineCacheKey {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for InlineCacheKey {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 impl fmt::Display for InlineCacheKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let key = String::from_utf8_lossy(&self.key);
@@ -84,6 +190,7 @@ impl fmt::Display for InlineCacheKey {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[repr(u32)]
 pub enum BlockCacheEntryState {
     Normal = 0,
@@ -103,9 +210,17 @@ impl From<i32> for BlockCacheEntryState {
     }
 }
 
-#[derive(Debug, FromZeroes, FromBytes, Clone, Copy)]
+#[derive(Debug, FromZeroes, FromBytes, AsBytes, Clone, Copy)]
+#[repr(C)]
 pub struct BlockCacheEntryStateField(i32);
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for BlockCacheEntryStateField {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.kind().serialize(serializer)
+    }
+}
+
 impl BlockCacheEntryStateField {
     // zerocopy lib doesn't provide a mechanism for decoding enums that don't represent all
     // states, see: https://github.com/google/zerocopy/issues/1429
@@ -115,7 +230,8 @@ impl BlockCacheEntryStateField {
 }
 
 // See: https://chromium.googlesource.com/chromium/src/net/+/ddbc6c5954c4bee29902082eb9052405e83abc02/disk_cache/disk_format.h#101
-#[derive(Debug, FromZeroes, FromBytes, Clone)]
+#[derive(Debug, FromZeroes, FromBytes, AsBytes, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[repr(C)]
 pub struct BlockFileCacheEntry {
     pub hash: u32,
@@ -130,6 +246,7 @@ pub struct BlockFileCacheEntry {
     pub data_size: [i32; 4],
     pub data_addr: [CacheAddr; 4],
     pub flags: u32,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pad: [u32; 4],
     pub self_hash: u32,
     pub key: InlineCacheKey,
@@ -137,6 +254,87 @@ pub struct BlockFileCacheEntry {
 
 sa::const_assert_eq!(mem::size_of::<BlockFileCacheEntry>(), 256);
 
+impl Describe for BlockFileCacheEntry {
+    fn describe(&self, offset: usize) -> StructDescription {
+        FieldWriter::new(self.as_bytes(), offset)
+            .hex("hash", self.hash)
+            .addr("next", self.next)
+            .addr("rankings_node", self.rankings_node)
+            .int("reuse_count", self.reuse_count)
+            .int("refetch_count", self.refetch_count)
+            .field("state", 4, format!("{:?}", self.state.kind()))
+            .time("creation_time", self.creation_time)
+            .int("key_len", self.key_len)
+            .addr("long_key", self.long_key)
+            .ints("data_size", &self.data_size)
+            .addrs("data_addr", &self.data_addr)
+            .hex("flags", self.flags)
+            .raw("pad", mem::size_of_val(&self.pad))
+            .hex("self_hash", self.self_hash)
+            .field(
+                "key",
+                INLINE_KEY_SIZE,
+                format!("{:?}", self.key.to_string()),
+            )
+            .finish("BlockFileCacheEntry")
+    }
+}
+
+// See: https://chromium.googlesource.com/chromium/src/net/+/refs/heads/main/disk_cache/blockfile/disk_format_v3.h
+/// The version 3.0 entry record, stored in the `BlockEntries` block files. Unlike the version 2
+/// entry, the key isn't stored inline and the rankings data is folded into the record.
+#[derive(Debug, FromZeroes, FromBytes, Clone)]
+#[repr(C)]
+pub struct EntryRecord {
+    pub hash: u32,
+    pad1: u32,
+    pub reuse_count: u8,
+    pub refetch_count: u8,
+    pub state: i8,
+    pub flags: u8,
+    pub key_len: i32,
+    pub data_size: [i32; 4],
+    pub data_addr: [CacheAddr; 4],
+    pub data_hash: [u32; 4],
+    pub creation_time: WindowsEpochMicroseconds,
+    pub last_modified_time: WindowsEpochMicroseconds,
+    pub last_access_time: WindowsEpochMicroseconds,
+    pad: [i32; 3],
+    pub self_hash: u32,
+}
+
+sa::const_assert_eq!(mem::size_of::<EntryRecord>(), 104);
+
+impl EntryRecord {
+    pub fn state(&self) -> BlockCacheEntryState {
+        BlockCacheEntryState::from(self.state as i32)
+    }
+}
+
+/// The version 3.0 record kept for evicted entries, stored in the `BlockEvicted` block files.
+#[derive(Debug, FromZeroes, FromBytes, Clone)]
+#[repr(C)]
+pub struct ShortEntryRecord {
+    pub hash: u32,
+    pad1: u32,
+    pub reuse_count: u8,
+    pub refetch_count: u8,
+    pub state: i8,
+    pub flags: u8,
+    pub key_len: i32,
+    pub last_access_time: WindowsEpochMicroseconds,
+    pub long_hash: [u32; 5],
+    pub self_hash: u32,
+}
+
+sa::const_assert_eq!(mem::size_of::<ShortEntryRecord>(), 48);
+
+impl ShortEntryRecord {
+    pub fn state(&self) -> BlockCacheEntryState {
+        BlockCacheEntryState::from(self.state as i32)
+    }
+}
+
 struct BlockFileStreamReader {
     addr: CacheAddr,
     size: usize,
@@ -169,7 +367,7 @@ impl Read for BlockFileStreamReader {
         let data_file = match data_files.get(self.addr.file_number()) {
             Ok(file) => file,
             Err(CCPError::Io { source }) => return Err(source),
-            Err(err) => return Err(io::Error::new(io::ErrorKind::Other, err)),
+            Err(err) => return Err(io::Error::other(err)),
         };
 
         let block_size = self
@@ -211,14 +409,73 @@ impl Read for ExternalFileReader {
         if let Some(file) = &mut self.file {
             file.read(buf)
         } else {
-            let file_name = format!("f_{:0>6x}", self.addr.file_number());
-            let reader = File::open(self.cache_path.join(file_name))?;
+            let reader = File::open(self.cache_path.join(self.addr.file_name()))?;
             self.file.replace(BufReader::new(reader));
             self.read(buf)
         }
     }
 }
 
+/// Create a reader for data of the given size stored at the given address. Streams without any
+/// data have an uninitialized address, so they're read as empty.
+fn stream_reader(
+    addr: CacheAddr,
+    size: i32,
+    data_files: &Rc<RefCell<DataFiles>>,
+    cache_path: &Path,
+) -> CCPResult<Box<dyn Read>> {
+    if !addr.is_initialized() || size <= 0 {
+        return Ok(Box::new(io::empty()));
+    }
+
+    match addr.file_type() {
+        FileType::External => Ok(Box::new(ExternalFileReader::new(
+            addr,
+            cache_path.to_path_buf(),
+        ))),
+        FileType::Block1k | FileType::Block256 | FileType::Block4k => Ok(Box::new(
+            BlockFileStreamReader::new(addr, size as usize, data_files.clone()),
+        )),
+        _ => Err(CCPError::InvalidState(format!(
+            "Requested stream reader of nonsense address type {:?}",
+            addr.file_type()
+        ))),
+    }
+}
+
+/// Create readers for the data streams of an entry, given their addresses and sizes.
+fn stream_readers(
+    data_addr: &[CacheAddr; 4],
+    data_size: &[i32; 4],
+    data_files: &Rc<RefCell<DataFiles>>,
+    cache_path: &Path,
+) -> Vec<CCPResult<Box<dyn Read>>> {
+    data_addr
+        .iter()
+        .zip(data_size.iter())
+        .map(|(addr, size)| stream_reader(*addr, *size, data_files, cache_path))
+        .collect()
+}
+
+/// Locate the data streams of an entry, given their addresses and sizes.
+fn stream_locations(
+    data_addr: &[CacheAddr; 4],
+    data_size: &[i32; 4],
+    cache_path: &Path,
+) -> CCPResult<Vec<Option<SourceLocation>>> {
+    data_addr
+        .iter()
+        .zip(data_size.iter())
+        .map(|(addr, size)| {
+            if addr.is_initialized() && *size > 0 {
+                SourceLocation::of(*addr, cache_path).map(Some)
+            } else {
+                Ok(None)
+            }
+        })
+        .collect()
+}
+
 /// An iterator over the logical entries in a map of block files. Data files are lazily loaded and
 /// cached. An entry in the chrome cache is a node in a linked list of entries in the block files.
 /// The index file is a hash table that maps keys to the first entry in the linked list.
@@ -264,7 +521,9 @@ impl DataFiles {
         DataFiles { data_files, path }
     }
 
-    fn get(&mut self, file_number: u32) -> CCPResult<&LazyBlockFile> {
+    /// Get the block file with the given number (i.e., `data_{file_number}`), loading it from disk
+    /// if it hasn't been loaded yet.
+    pub fn get(&mut self, file_number: u32) -> CCPResult<&LazyBlockFile> {
         Ok(match self.data_files.entry(file_number) {
             Entry::Occupied(entry) => entry.into_mut(),
             Entry::Vacant(entry) => {
@@ -272,11 +531,49 @@ impl DataFiles {
                 let mut file = fs::File::open(&file_path)?;
                 let mut buf: Vec<u8> = Vec::new();
                 file.read_to_end(&mut buf)?;
-                entry.insert(LazyBlockFile::new(Rc::new(buf)))
+                entry.insert(LazyBlockFile::new(Rc::new(buf), file_path))
             }
         })
     }
 
+    /// Get every block file holding blocks of the given type. Starting at the first file for the
+    /// type, each file's header links to the next one created once it filled up.
+    pub fn chain(&mut self, file_type: FileType) -> CCPResult<Vec<LazyBlockFile>> {
+        let mut files = Vec::new();
+        let mut visited = HashSet::new();
+        let mut file_number = file_type.first_block_file()?;
+
+        loop {
+            if !visited.insert(file_number) {
+                return Err(CCPError::InvalidData(format!(
+                    "block file chain for {:?} loops back to data_{}",
+                    file_type, file_number
+                )));
+            }
+
+            let file = self.get(file_number)?.clone();
+            let header = file.header()?;
+
+            if header.this_file as u32 != file_number {
+                return Err(CCPError::InvalidData(format!(
+                    "data_{} claims to be data_{}",
+                    file_number, header.this_file
+                )));
+            }
+
+            let next_file = header.next_file;
+            files.push(file);
+
+            // data_0 is never a continuation, so a zero link marks the end of the chain.
+            if next_file <= 0 {
+                break;
+            }
+            file_number = next_file as u32;
+        }
+
+        Ok(files)
+    }
+
     pub fn get_entry(&mut self, addr: &CacheAddr) -> CCPResult<BufferSlice> {
         let data_file = self.get(addr.file_number())?;
         data_file.get_buffer(addr)
@@ -291,9 +588,11 @@ impl Iterator for LazyBlockFileCacheEntryIterator {
 
         let mut data_files = (*self.data_files).borrow_mut();
 
+        let location = SourceLocation::of(current, &self.cache_path).ok()?;
         let current = data_files.get_entry(&current).ok()?;
         let current = LazyBlockFileCacheEntry::new(
             current,
+            location,
             Rc::clone(&self.data_files),
             self.cache_path.clone(),
         );
@@ -309,8 +608,95 @@ impl Iterator for LazyBlockFileCacheEntryIterator {
     }
 }
 
+/// A version 3.0 entry record, referenced by a cell of the index table.
+#[derive(Debug)]
+pub enum EntryRecordRef<'a> {
+    Entry(&'a EntryRecord),
+    Evicted(&'a ShortEntryRecord),
+}
+
+/// A lazily parsed version 3.0 entry record, along with the index cell that references it.
+pub struct LazyEntryRecord {
+    cell: IndexCell,
+    buffer: BufferSlice,
+    location: SourceLocation,
+    data_files: Rc<RefCell<DataFiles>>,
+    cache_path: PathBuf,
+}
+
+impl LazyEntryRecord {
+    pub fn new(
+        cell: IndexCell,
+        buffer: BufferSlice,
+        location: SourceLocation,
+        data_files: Rc<RefCell<DataFiles>>,
+        cache_path: PathBuf,
+    ) -> LazyEntryRecord {
+        LazyEntryRecord {
+            cell,
+            buffer,
+            location,
+            data_files,
+            cache_path,
+        }
+    }
+
+    pub fn cell(&self) -> &IndexCell {
+        &self.cell
+    }
+
+    /// Where the record was read from.
+    pub fn location(&self) -> &SourceLocation {
+        &self.location
+    }
+
+    /// Parse the record from the buffer, choosing the layout by the group of the index cell.
+    pub fn get(&self) -> CCPResult<EntryRecordRef<'_>> {
+        let misalignment =
+            || CCPError::DataMisalignment(format!("entry record at {}", self.buffer.start));
+
+        match self.cell.group() {
+            EntryGroup::Evicted => ShortEntryRecord::ref_from(self.buffer.get())
+                .map(EntryRecordRef::Evicted)
+                .ok_or_else(misalignment),
+            _ => EntryRecord::ref_from(self.buffer.get())
+                .map(EntryRecordRef::Entry)
+                .ok_or_else(misalignment),
+        }
+    }
+
+    /// Return readers for the data streams of the entry, see `stream_locations` for where they're
+    /// stored. Evicted entries don't keep any data.
+    pub fn stream_readers(&self) -> CCPResult<Vec<CCPResult<Box<dyn Read>>>> {
+        match self.get()? {
+            EntryRecordRef::Entry(record) => Ok(stream_readers(
+                &record.data_addr,
+                &record.data_size,
+                &self.data_files,
+                &self.cache_path,
+            )),
+            EntryRecordRef::Evicted(_) => Err(CCPError::InvalidState(
+                "evicted entries have no data streams".to_string(),
+            )),
+        }
+    }
+
+    /// Where each data stream of the entry is stored. Evicted entries don't keep any data.
+    pub fn stream_locations(&self) -> CCPResult<Vec<Option<SourceLocation>>> {
+        match self.get()? {
+            EntryRecordRef::Entry(record) => {
+                stream_locations(&record.data_addr, &record.data_size, &self.cache_path)
+            }
+            EntryRecordRef::Evicted(_) => Err(CCPError::InvalidState(
+                "evicted entries have no data streams".to_string(),
+            )),
+        }
+    }
+}
+
 pub struct LazyRankingsNode {
     buffer: BufferSlice,
+    location: SourceLocation,
 }
 
 /// A slice to a shared buffer. Enables us to pass a reference to the buffer to all of the
@@ -333,6 +719,15 @@ impl BufferSlice {
     pub fn get(&self) -> &[u8] {
         &self.buffer[self.start..self.start + self.size]
     }
+
+    /// The offset of the slice within the underlying buffer.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
 }
 
 impl LazyRankingsNode {
@@ -342,10 +737,21 @@ impl LazyRankingsNode {
             self.buffer.start
         )))
     }
+
+    /// Where the node was read from.
+    pub fn location(&self) -> &SourceLocation {
+        &self.location
+    }
+
+    /// Dump every field of the node along with its absolute offset within the block file.
+    pub fn describe(&self) -> CCPResult<StructDescription> {
+        Ok(self.get()?.describe(self.location.offset))
+    }
 }
 
 pub struct LazyBlockFileCacheEntry {
     buffer: BufferSlice,
+    location: SourceLocation,
     data_files: Rc<RefCell<DataFiles>>,
     cache_path: PathBuf,
 }
@@ -353,16 +759,34 @@ pub struct LazyBlockFileCacheEntry {
 impl LazyBlockFileCacheEntry {
     pub fn new(
         buffer: BufferSlice,
+        location: SourceLocation,
         block_files: Rc<RefCell<DataFiles>>,
         cache_path: PathBuf,
     ) -> LazyBlockFileCacheEntry {
         LazyBlockFileCacheEntry {
             buffer,
+            location,
             data_files: block_files,
             cache_path,
         }
     }
 
+    /// Where the entry was read from.
+    pub fn location(&self) -> &SourceLocation {
+        &self.location
+    }
+
+    /// Dump every field of the entry along with its absolute offset within the block file.
+    pub fn describe(&self) -> CCPResult<StructDescription> {
+        Ok(self.get()?.describe(self.location.offset))
+    }
+
+    /// Where each data stream of the entry is stored, or `None` for streams without data.
+    pub fn stream_locations(&self) -> CCPResult<Vec<Option<SourceLocation>>> {
+        let entry = self.get()?;
+        stream_locations(&entry.data_addr, &entry.data_size, &self.cache_path)
+    }
+
     /// Parse the entry from the buffer and return a reference to it.
     pub fn get(&self) -> CCPResult<&BlockFileCacheEntry> {
         BlockFileCacheEntry::ref_from(self.buffer.get()).ok_or(error::CCPError::DataMisalignment(
@@ -370,35 +794,165 @@ impl LazyBlockFileCacheEntry {
         ))
     }
 
+    /// The full key of the entry. Keys that don't fit in the inline key field continue into the
+    /// following blocks of the entry, or are stored out of line when they don't fit there either.
+    pub fn key(&self) -> CCPResult<String> {
+        let entry = self.get()?;
+        let key_len = entry.key_len.max(0) as usize;
+
+        let key = if entry.long_key.is_initialized() {
+            let mut key = Vec::with_capacity(key_len);
+            stream_reader(
+                entry.long_key,
+                entry.key_len,
+                &self.data_files,
+                &self.cache_path,
+            )?
+            .take(key_len as u64)
+            .read_to_end(&mut key)?;
+            key
+        } else {
+            let buffer = &self.buffer.buffer;
+            let entry_end =
+                self.buffer.start + self.buffer.size * self.location.addr.num_blocks() as usize;
+            let start = self.buffer.start + INLINE_KEY_OFFSET;
+            let end = min(start + key_len, min(entry_end, buffer.len()));
+            buffer[start..end].to_vec()
+        };
+
+        Ok(String::from_utf8_lossy(&key)
+            .trim_end_matches(char::from(0))
+            .to_string())
+    }
+
+    /// Return a reader for one of the entry's data streams. The reader only yields the stream's
+    /// bytes; where they're stored is given by `stream_locations`.
+    pub fn stream_reader(&self, index: usize) -> CCPResult<Box<dyn Read>> {
+        let entry = self.get()?;
+        let (addr, size) = entry
+            .data_addr
+            .iter()
+            .zip(entry.data_size.iter())
+            .nth(index)
+            .ok_or(CCPError::InvalidState(format!(
+                "entries have no stream {}",
+                index
+            )))?;
+
+        stream_reader(*addr, *size, &self.data_files, &self.cache_path)
+    }
+
+    /// Parse the response info (status line, headers and request/response times) stored in the
+    /// entry's first stream.
+    pub fn response_info(&self) -> CCPResult<HttpResponseInfo> {
+        let mut data = Vec::new();
+        self.stream_reader(0)?.read_to_end(&mut data)?;
+        HttpResponseInfo::from_pickle(&data)
+    }
+
+    /// Return a reader for the response body in the entry's second stream, with any
+    /// `Content-Encoding` from the response headers undone.
+    pub fn decoded_body_reader(&self) -> CCPResult<Box<dyn Read>> {
+        let info = self.response_info()?;
+        let body = self.stream_reader(1)?;
+
+        match info.headers.get("content-encoding") {
+            Some(content_encoding) => content_encoding::decoder(body, content_encoding),
+            None => Ok(body),
+        }
+    }
+
+    /// Write the cached response as a raw HTTP/1.1 response: the status line and headers followed
+    /// by the body. Returns the number of bytes written.
+    ///
+    /// Decoded bodies are streamed twice when fixing up `Content-Length`, once to measure them and
+    /// once to write them, rather than being held in memory.
+    pub fn write_http_response<W: Write>(
+        &self,
+        mut writer: W,
+        options: &HttpResponseOptions,
+    ) -> CCPResult<u64> {
+        let info = self.response_info()?;
+        let decode = options.decode
+            && info
+                .headers
+                .get("content-encoding")
+                .is_some_and(content_encoding::is_supported);
+
+        let body = || {
+            if decode {
+                self.decoded_body_reader()
+            } else {
+                self.stream_reader(1)
+            }
+        };
+
+        let content_length = if options.fix_content_length {
+            Some(io::copy(&mut body()?, &mut io::sink())?)
+        } else {
+            None
+        };
+
+        let head = response_head(&info.headers, decode, content_length);
+        writer.write_all(&head)?;
+        let written = head.len() as u64 + io::copy(&mut body()?, &mut writer)?;
+
+        writer.flush()?;
+        Ok(written)
+    }
+
+    /// The cached response as a raw HTTP/1.1 response, see
+    /// `LazyBlockFileCacheEntry::write_http_response`.
+    pub fn to_http_response_bytes(&self, options: &HttpResponseOptions) -> CCPResult<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.write_http_response(&mut bytes, options)?;
+        Ok(bytes)
+    }
+
+    /// The endpoint the cached response was received from, if chrome recorded one.
+    pub fn remote_endpoint(&self) -> CCPResult<Option<RemoteEndpoint>> {
+        Ok(self.response_info()?.remote_endpoint)
+    }
+
+    /// The freshness of the cached response at the given instant, i.e. whether it would be used
+    /// without revalidation.
+    pub fn freshness_at(&self, now: DateTime<Utc>) -> CCPResult<Freshness> {
+        let info = self.response_info()?;
+        Ok(Freshness::compute(
+            &info.headers,
+            info.request_time.into_datetime_utc()?,
+            info.response_time.into_datetime_utc()?,
+            now,
+        ))
+    }
+
+    /// The declared type of the response body along with the type sniffed from its first bytes,
+    /// after undoing any `Content-Encoding`.
+    pub fn content_types(&self) -> CCPResult<ContentTypes> {
+        let info = self.response_info()?;
+        let body = match self.decoded_body_reader() {
+            Ok(body) => body,
+            Err(_) => self.stream_reader(1)?,
+        };
+
+        let mut prefix = Vec::with_capacity(SNIFF_LEN);
+        body.take(SNIFF_LEN as u64).read_to_end(&mut prefix)?;
+        Ok(ContentTypes::new(info.headers.get("content-type"), &prefix))
+    }
+
     /// Return readers for the actual cache data. Typically, this is a header stream followed by
     /// a content stream.
-    pub fn stream_readers(self) -> CCPResult<Vec<CCPResult<Box<dyn Read>>>> {
+    pub fn stream_readers(&self) -> CCPResult<Vec<CCPResult<Box<dyn Read>>>> {
         let entry = self.get().or(Err(CCPError::InvalidState(
             "Unable to read entry".to_string(),
         )))?;
 
-        Ok(entry
-            .data_addr
-            .iter()
-            .zip(entry.data_size.iter())
-            .map(|(addr, size)| match addr.file_type() {
-                FileType::External => Ok(Box::new(ExternalFileReader::new(
-                    *addr,
-                    self.cache_path.clone(),
-                )) as Box<dyn Read>),
-                FileType::Block1k | FileType::Block256 | FileType::Block4k => Ok(Box::new(
-                    BlockFileStreamReader::new(*addr, *size as usize, self.data_files.clone()),
-                )
-                    as Box<dyn Read>),
-                _ => Err(CCPError::InvalidState(
-                    format!(
-                        "Requested stream reader of nonsense address type {:?}",
-                        addr.file_type()
-                    )
-                    .to_string(),
-                )),
-            })
-            .collect())
+        Ok(stream_readers(
+            &entry.data_addr,
+            &entry.data_size,
+            &self.data_files,
+            &self.cache_path,
+        ))
     }
 
     pub fn get_rankings_node(&mut self) -> CCPResult<LazyRankingsNode> {
@@ -410,28 +964,37 @@ impl LazyBlockFileCacheEntry {
             ));
         }
 
+        let location = SourceLocation::of(cache_entry.rankings_node, &self.cache_path)?;
         let mut data_files = self.data_files.borrow_mut();
         let ranking_entry = data_files.get_entry(&cache_entry.rankings_node)?;
 
         Ok(LazyRankingsNode {
             buffer: ranking_entry,
+            location,
         })
     }
 }
 
+#[derive(Clone)]
 pub struct LazyBlockFile {
     buffer: Rc<Vec<u8>>,
+    path: PathBuf,
 }
 
 /// Represents a block file in the chrome cache. It has a header, providing some metadata about the
 /// file, followed by a series of contiguous blocks of a fixed size, defined by a field within the
 /// header.
 impl LazyBlockFile {
-    pub fn new(buffer: Rc<Vec<u8>>) -> LazyBlockFile {
-        LazyBlockFile { buffer }
+    pub fn new(buffer: Rc<Vec<u8>>, path: PathBuf) -> LazyBlockFile {
+        LazyBlockFile { buffer, path }
+    }
+
+    /// The path the block file was loaded from.
+    pub fn path(&self) -> &Path {
+        &self.path
     }
 
-    fn header(&self) -> CCPResult<&BlockFileHeader> {
+    pub fn header(&self) -> CCPResult<&BlockFileHeader> {
         let header = BlockFileHeader::ref_from(&self.buffer[0..mem::size_of::<BlockFileHeader>()])
             .ok_or(error::CCPError::DataMisalignment(
                 "block file header".to_string(),
@@ -1,5 +1,5 @@
 use std::fmt::{self, Debug, Formatter};
-use zerocopy::{FromBytes, FromZeroes};
+use zerocopy::{AsBytes, FromBytes, FromZeroes};
 
 use crate::{CCPError, CCPResult};
 
@@ -14,7 +14,7 @@ const START_BLOCK_MASK: u32 = 0x0000FFFF;
 const NUM_BLOCKS_MASK: u32 = 0x03000000;
 const NUM_BLOCKS_OFFSET: u32 = 24;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum FileType {
     External = 0,
     Rankings = 1,
@@ -29,18 +29,34 @@ pub enum FileType {
 impl FileType {
     pub fn block_size(&self) -> CCPResult<usize> {
         match &self {
+            FileType::Rankings => Ok(36),
             FileType::Block256 => Ok(256),
             FileType::Block1k => Ok(1024),
             FileType::Block4k => Ok(1024 * 4),
-            _ => Err(CCPError::InvalidState(
-                "Block size requested for non block file".to_string(),
+            FileType::BlockFiles => Ok(8),
+            FileType::BlockEntries => Ok(104),
+            FileType::BlockEvicted => Ok(48),
+            FileType::External => Err(CCPError::InvalidState(
+                "Block size requested for external file".to_string(),
             )),
         }
     }
+
+    /// The number of the first block file (`data_N`) holding blocks of this type. Further files of
+    /// the same type are linked from it via `BlockFileHeader::next_file`.
+    pub fn first_block_file(&self) -> CCPResult<u32> {
+        match &self {
+            FileType::External => Err(CCPError::InvalidState(
+                "Block file requested for external file type".to_string(),
+            )),
+            _ => Ok(*self as u32 - 1),
+        }
+    }
 }
 
 // See: https://chromium.googlesource.com/chromium/src/net/+/ddbc6c5954c4bee29902082eb9052405e83abc02/disk_cache/disk_format_base.h#28
-#[derive(Copy, Clone, FromZeroes, FromBytes)]
+#[derive(Copy, Clone, FromZeroes, FromBytes, AsBytes)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
 #[repr(C)]
 pub struct CacheAddr {
     pub value: u32,
@@ -81,6 +97,15 @@ impl CacheAddr {
         }
     }
 
+    /// The name of the file the address points into: `f_XXXXXX` for external files and `data_N`
+    /// for block files.
+    pub fn file_name(&self) -> String {
+        match self.file_type() {
+            FileType::External => format!("f_{:0>6x}", self.file_number()),
+            _ => format!("data_{}", self.file_number()),
+        }
+    }
+
     pub fn start_block(&self) -> u32 {
         self.value & START_BLOCK_MASK
     }
@@ -1,7 +1,11 @@
 use std::mem;
-use zerocopy::{FromBytes, FromZeroes};
+use zerocopy::{AsBytes, FromBytes, FromZeroes};
 
-use crate::{time::WindowsEpochMicroseconds, CacheAddr};
+use crate::{
+    describe::{Describe, FieldWriter, StructDescription},
+    time::WindowsEpochMicroseconds,
+    CacheAddr,
+};
 use static_assertions as sa;
 
 pub const INDEX_MAGIC: u32 = 0xc103cac3;
@@ -10,6 +14,7 @@ pub const INDEX_HEADER_SIZE: usize = mem::size_of::<IndexHeader>();
 sa::const_assert_eq!(INDEX_HEADER_SIZE, 368);
 
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum CacheVersion {
     Version2_0,
     Version2_1,
@@ -28,11 +33,14 @@ impl From<CacheVersionId> for CacheVersion {
     }
 }
 
-#[derive(Debug, FromZeroes, FromBytes, Copy, Clone)]
+#[derive(Debug, FromZeroes, FromBytes, AsBytes, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[repr(C)]
 pub struct CacheVersionId(u32);
 
 // See: https://chromium.googlesource.com/chromium/src/net/+/refs/heads/main/disk_cache/blockfile/disk_format.h#77
-#[derive(Debug, FromZeroes, FromBytes)]
+#[derive(Debug, FromZeroes, FromBytes, AsBytes)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[repr(C)]
 pub struct IndexHeader {
     pub magic: u32,
@@ -46,14 +54,17 @@ pub struct IndexHeader {
     pub crash: i32,
     pub experiment: i32,
     pub create_time: WindowsEpochMicroseconds,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pad: [u32; 52],
     pub lru: LruData,
 }
 
 // See: https://chromium.googlesource.com/chromium/src/net/+/refs/heads/main/disk_cache/blockfile/disk_format.h#64
-#[derive(Debug, FromZeroes, FromBytes)]
+#[derive(Debug, FromZeroes, FromBytes, AsBytes)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[repr(C)]
 pub struct LruData {
+    #[cfg_attr(feature = "serde", serde(skip))]
     pad1: [u32; 2],
     pub filled: i32,
     pub sizes: [i32; 5],
@@ -62,5 +73,39 @@ pub struct LruData {
     pub transaction: CacheAddr,
     pub operation: i32,
     pub operation_list: i32,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pad2: [u32; 7],
 }
+
+impl Describe for IndexHeader {
+    fn describe(&self, offset: usize) -> StructDescription {
+        let lru = &self.lru;
+        FieldWriter::new(self.as_bytes(), offset)
+            .hex("magic", self.magic)
+            .field(
+                "version",
+                4,
+                format!("{:?}", CacheVersion::from(self.version)),
+            )
+            .int("num_entries", self.num_entries)
+            .int("num_bytes", self.num_bytes)
+            .int("last_file", self.last_file)
+            .int("this_id", self.this_id)
+            .addr("stats", self.stats)
+            .int("table_len", self.table_len)
+            .int("crash", self.crash)
+            .int("experiment", self.experiment)
+            .time("create_time", self.create_time)
+            .raw("pad", mem::size_of_val(&self.pad))
+            .raw("lru.pad1", mem::size_of_val(&lru.pad1))
+            .int("lru.filled", lru.filled)
+            .ints("lru.sizes", &lru.sizes)
+            .addrs("lru.heads", &lru.heads)
+            .addrs("lru.tails", &lru.tails)
+            .addr("lru.transaction", lru.transaction)
+            .int("lru.operation", lru.operation)
+            .int("lru.operation_list", lru.operation_list)
+            .raw("lru.pad2", mem::size_of_val(&lru.pad2))
+            .finish("IndexHeader")
+    }
+}
@@ -0,0 +1,384 @@
+use std::mem;
+use zerocopy::{FromBytes, FromZeroes, Ref};
+
+use crate::{
+    cache_address::FileType, cache_index::CacheVersionId, time::WindowsEpochMicroseconds, CCPError,
+    CCPResult, CacheAddr,
+};
+use static_assertions as sa;
+
+const BASE_BITMAP_BYTES: usize = 3968;
+pub const CELLS_PER_BUCKET: usize = 4;
+
+const CELL_ADDRESS_MASK: u64 = (1 << 22) - 1;
+const CELL_HASH_OFFSET: u64 = 22;
+const CELL_HASH_MASK: u64 = (1 << 18) - 1;
+const CELL_TIMESTAMP_OFFSET: u64 = 40;
+const CELL_TIMESTAMP_MASK: u64 = (1 << 20) - 1;
+const CELL_REUSE_OFFSET: u64 = 60;
+const CELL_STATE_MASK: u8 = 0x07;
+const CELL_GROUP_OFFSET: u8 = 3;
+const CELL_GROUP_MASK: u8 = 0x07;
+const CELL_SUM_OFFSET: u8 = 6;
+
+const ADDR_INITIALIZED_MASK: u32 = 0x80000000;
+const ADDR_FILE_TYPE_OFFSET: u32 = 28;
+
+const MICROSEC_PER_MINUTE: u64 = 60 * 1_000_000;
+
+pub const INDEX_BITMAP_SIZE: usize = mem::size_of::<IndexBitmap>();
+sa::const_assert_eq!(mem::size_of::<IndexHeaderV3>(), 128);
+sa::const_assert_eq!(INDEX_BITMAP_SIZE, 4096);
+sa::const_assert_eq!(mem::size_of::<IndexCell>(), 9);
+sa::const_assert_eq!(mem::size_of::<IndexBucket>(), 44);
+
+// See: https://chromium.googlesource.com/chromium/src/net/+/refs/heads/main/disk_cache/blockfile/disk_format_v3.h
+#[derive(Debug, FromZeroes, FromBytes)]
+#[repr(C)]
+pub struct IndexHeaderV3 {
+    pub magic: u32,
+    pub version: CacheVersionId,
+    pub num_entries: i32,
+    pub num_evicted_entries: i32,
+    pub num_bytes: i64,
+    pub last_file: i32,
+    reserved1: i32,
+    pub stats: CacheAddr,
+    pub table_len: i32,
+    pub crash: i32,
+    pub experiment: i32,
+    pub max_bytes: i32,
+    pub flags: u32,
+    pub used_cells: i32,
+    pub max_bucket: i32,
+    pub create_time: WindowsEpochMicroseconds,
+    pub base_time: WindowsEpochMicroseconds,
+    pub old_time: WindowsEpochMicroseconds,
+    pub max_block_file: i32,
+    pub num_no_use_entries: i32,
+    pub num_low_use_entries: i32,
+    pub num_high_use_entries: i32,
+    reserved: i32,
+    pub num_empty_entries: i32,
+    pad: [i32; 4],
+}
+
+/// The first page of a version 3.0 index file: the header, followed by the start of the bitmap of
+/// used cells.
+#[derive(Debug, FromZeroes, FromBytes)]
+#[repr(C)]
+pub struct IndexBitmap {
+    pub header: IndexHeaderV3,
+    pub bitmap: [u32; BASE_BITMAP_BYTES / 4],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CellState {
+    Free,
+    New,
+    Open,
+    Modified,
+    Deleted,
+    Fixing,
+    Used,
+    Unknown(u8),
+}
+
+impl From<u8> for CellState {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => CellState::Free,
+            1 => CellState::New,
+            2 => CellState::Open,
+            3 => CellState::Modified,
+            4 => CellState::Deleted,
+            5 => CellState::Fixing,
+            6 => CellState::Used,
+            _ => CellState::Unknown(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntryGroup {
+    NoUse,
+    LowUse,
+    HighUse,
+    Reserved,
+    Evicted,
+    Unknown(u8),
+}
+
+impl From<u8> for EntryGroup {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => EntryGroup::NoUse,
+            1 => EntryGroup::LowUse,
+            2 => EntryGroup::HighUse,
+            3 => EntryGroup::Reserved,
+            4 => EntryGroup::Evicted,
+            _ => EntryGroup::Unknown(value),
+        }
+    }
+}
+
+/// A cell of the version 3.0 index table. The fields are packed into bitfields, so they're decoded
+/// on access.
+#[derive(FromZeroes, FromBytes, Clone, Copy)]
+#[repr(C)]
+pub struct IndexCell {
+    first_part: [u8; 8],
+    last_part: u8,
+}
+
+impl IndexCell {
+    fn first_part(&self) -> u64 {
+        u64::from_le_bytes(self.first_part)
+    }
+
+    /// The file number and start block of the entry record, without the file type.
+    pub fn address_value(&self) -> u32 {
+        (self.first_part() & CELL_ADDRESS_MASK) as u32
+    }
+
+    /// The bits of the entry's hash that aren't implied by the bucket holding the cell.
+    pub fn hash(&self) -> u32 {
+        ((self.first_part() >> CELL_HASH_OFFSET) & CELL_HASH_MASK) as u32
+    }
+
+    /// Minutes since the index header's `base_time`.
+    pub fn timestamp(&self) -> u32 {
+        ((self.first_part() >> CELL_TIMESTAMP_OFFSET) & CELL_TIMESTAMP_MASK) as u32
+    }
+
+    pub fn reuse(&self) -> u8 {
+        (self.first_part() >> CELL_REUSE_OFFSET) as u8
+    }
+
+    pub fn state(&self) -> CellState {
+        CellState::from(self.last_part & CELL_STATE_MASK)
+    }
+
+    pub fn group(&self) -> EntryGroup {
+        EntryGroup::from((self.last_part >> CELL_GROUP_OFFSET) & CELL_GROUP_MASK)
+    }
+
+    pub fn sum(&self) -> u8 {
+        self.last_part >> CELL_SUM_OFFSET
+    }
+
+    pub fn is_used(&self) -> bool {
+        self.address_value() != 0
+    }
+
+    /// The address of the entry record. Evicted entries keep a short record in the evicted block
+    /// files, everything else a full record in the entry block files.
+    pub fn address(&self) -> CacheAddr {
+        let file_type = match self.group() {
+            EntryGroup::Evicted => FileType::BlockEvicted,
+            _ => FileType::BlockEntries,
+        };
+
+        CacheAddr::from(
+            ADDR_INITIALIZED_MASK
+                + ((file_type as u32) << ADDR_FILE_TYPE_OFFSET)
+                + self.address_value(),
+        )
+    }
+}
+
+impl std::fmt::Debug for IndexCell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IndexCell")
+            .field("address", &self.address())
+            .field("hash", &self.hash())
+            .field("timestamp", &self.timestamp())
+            .field("reuse", &self.reuse())
+            .field("state", &self.state())
+            .field("group", &self.group())
+            .field("sum", &self.sum())
+            .finish()
+    }
+}
+
+#[derive(Debug, FromZeroes, FromBytes, Clone, Copy)]
+#[repr(C)]
+pub struct IndexBucket {
+    pub cells: [IndexCell; CELLS_PER_BUCKET],
+    /// The cell number of the first cell of the next bucket in the extra table, or 0.
+    pub next: i32,
+    pub hash: u32,
+}
+
+/// A used cell of the index table, along with the number of the main bucket it hashes to.
+#[derive(Debug, Clone, Copy)]
+pub struct IndexTableCell {
+    pub bucket: usize,
+    pub cell: IndexCell,
+}
+
+/// The version 3.0 index. The bitmap lives in the `index` file while the hash table is split into
+/// a main table (`index_tb1`), with one bucket per hash value, and an extra table (`index_tb2`)
+/// holding the buckets chained from full main buckets.
+pub struct IndexTableV3 {
+    bitmap: Vec<u8>,
+    main_table: Vec<u8>,
+    extra_table: Vec<u8>,
+}
+
+impl IndexTableV3 {
+    pub fn new(bitmap: Vec<u8>, main_table: Vec<u8>, extra_table: Vec<u8>) -> IndexTableV3 {
+        IndexTableV3 {
+            bitmap,
+            main_table,
+            extra_table,
+        }
+    }
+
+    pub fn bitmap(&self) -> CCPResult<&IndexBitmap> {
+        if self.bitmap.len() < INDEX_BITMAP_SIZE {
+            return Err(CCPError::InvalidData(
+                "index bitmap is truncated".to_string(),
+            ));
+        }
+
+        IndexBitmap::ref_from(&self.bitmap[0..INDEX_BITMAP_SIZE]).ok_or(CCPError::DataMisalignment(
+            "index bitmap misalignment".to_string(),
+        ))
+    }
+
+    pub fn header(&self) -> CCPResult<&IndexHeaderV3> {
+        Ok(&self.bitmap()?.header)
+    }
+
+    fn buckets(table: &[u8]) -> CCPResult<&[IndexBucket]> {
+        let len = table.len() - table.len() % mem::size_of::<IndexBucket>();
+        Ref::<_, [IndexBucket]>::new_slice(&table[..len])
+            .map(|buckets| buckets.into_slice())
+            .ok_or(CCPError::DataMisalignment(
+                "index table misalignment".to_string(),
+            ))
+    }
+
+    pub fn main_buckets(&self) -> CCPResult<&[IndexBucket]> {
+        let num_buckets = self.header()?.table_len.max(0) as usize / CELLS_PER_BUCKET;
+        let buckets = Self::buckets(&self.main_table)?;
+
+        buckets
+            .get(..num_buckets)
+            .ok_or(CCPError::InvalidData(format!(
+                "main index table holds {} buckets, expected {}",
+                buckets.len(),
+                num_buckets
+            )))
+    }
+
+    pub fn extra_buckets(&self) -> CCPResult<&[IndexBucket]> {
+        Self::buckets(&self.extra_table)
+    }
+
+    /// Convert a cell's timestamp into an absolute time.
+    pub fn cell_time(&self, cell: &IndexCell) -> CCPResult<WindowsEpochMicroseconds> {
+        let base_time = self.header()?.base_time.as_micros();
+        (cell.timestamp() as u64)
+            .checked_mul(MICROSEC_PER_MINUTE)
+            .and_then(|offset| base_time.checked_add(offset))
+            .map(WindowsEpochMicroseconds::new)
+            .ok_or(CCPError::InvalidData(format!(
+                "cell timestamp {} is out of range of base time {}",
+                cell.timestamp(),
+                base_time
+            )))
+    }
+
+    /// Every used cell of the table, walking each main bucket and the extra buckets chained from
+    /// it.
+    pub fn cells(&self) -> CCPResult<Vec<IndexTableCell>> {
+        let main = self.main_buckets()?;
+        let extra = self.extra_buckets()?;
+        let min_extra_bucket = main.len();
+
+        let mut cells = Vec::new();
+        for (bucket_num, bucket) in main.iter().enumerate() {
+            let mut bucket = bucket;
+            // Guard against chains that loop, which can't be longer than the extra table.
+            let mut remaining = extra.len() + 1;
+
+            loop {
+                cells.extend(
+                    bucket
+                        .cells
+                        .iter()
+                        .filter(|cell| cell.is_used())
+                        .map(|cell| IndexTableCell {
+                            bucket: bucket_num,
+                            cell: *cell,
+                        }),
+                );
+
+                remaining -= 1;
+                if bucket.next <= 0 || remaining == 0 {
+                    break;
+                }
+
+                let next = bucket.next as usize / CELLS_PER_BUCKET;
+                match next
+                    .checked_sub(min_extra_bucket)
+                    .and_then(|index| extra.get(index))
+                {
+                    Some(next_bucket) => bucket = next_bucket,
+                    None => break,
+                }
+            }
+        }
+
+        Ok(cells)
+    }
+}
+
+#[test]
+fn test_index_cell_bitfields() {
+    let first_part: u64 = 0x12345
+        | (0x2abcd << CELL_HASH_OFFSET)
+        | (0x54321 << CELL_TIMESTAMP_OFFSET)
+        | (0x9 << CELL_REUSE_OFFSET);
+    let cell = IndexCell {
+        first_part: first_part.to_le_bytes(),
+        last_part: 0b10_100_110,
+    };
+
+    assert_eq!(cell.address_value(), 0x12345);
+    assert_eq!(cell.hash(), 0x2abcd);
+    assert_eq!(cell.timestamp(), 0x54321);
+    assert_eq!(cell.reuse(), 0x9);
+    assert_eq!(cell.state(), CellState::Used);
+    assert_eq!(cell.group(), EntryGroup::Evicted);
+    assert_eq!(cell.sum(), 0b10);
+    assert_eq!(cell.address().file_type(), FileType::BlockEvicted);
+    assert_eq!(cell.address().file_number(), 0x1);
+    assert_eq!(cell.address().start_block(), 0x2345);
+}
+
+#[test]
+fn test_cell_time() {
+    let mut bitmap = vec![0; INDEX_BITMAP_SIZE];
+    let base_time = mem::offset_of!(IndexHeaderV3, base_time);
+    let cell = IndexCell {
+        first_part: (2u64 << CELL_TIMESTAMP_OFFSET).to_le_bytes(),
+        last_part: 0,
+    };
+
+    bitmap[base_time..base_time + 8].copy_from_slice(&1_000u64.to_le_bytes());
+    let table = IndexTableV3::new(bitmap.clone(), Vec::new(), Vec::new());
+    assert_eq!(
+        table.cell_time(&cell).unwrap().as_micros(),
+        1_000 + 2 * MICROSEC_PER_MINUTE
+    );
+
+    bitmap[base_time..base_time + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+    let table = IndexTableV3::new(bitmap, Vec::new(), Vec::new());
+    assert!(matches!(
+        table.cell_time(&cell),
+        Err(CCPError::InvalidData(_))
+    ));
+}
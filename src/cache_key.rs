@@ -0,0 +1,113 @@
+//! Parsing of the keys chrome stores entries under. Besides the URL of the resource, keys of
+//! caches partitioned by network isolation key are prefixed with the sites the resource was
+//! loaded from.
+//!
+//! See: https://chromium.googlesource.com/chromium/src/net/+/refs/heads/main/http/http_cache.cc
+const DOUBLE_KEY_PREFIX: &str = "_dk_";
+const SUBFRAME_DOCUMENT_RESOURCE_PREFIX: &str = "s_";
+const CREDENTIALLESS_PREFIX: &str = "cn_";
+
+/// The parts of a cache key, e.g.
+/// `1/0/_dk_https://example.com https://example.com https://example.com/index.html`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CacheKey {
+    /// The URL of the cached resource.
+    pub url: String,
+    /// The identifier of the uploaded data the request carried, if the key has one.
+    pub upload_identifier: Option<i64>,
+    /// The site of the top-level frame the resource was loaded from, for partitioned caches.
+    pub top_frame_site: Option<String>,
+    /// The site of the frame the resource was loaded from, for triple-keyed caches.
+    pub frame_site: Option<String>,
+    /// Whether the resource was a document loaded into a subframe.
+    pub subframe_document_resource: bool,
+}
+
+impl CacheKey {
+    /// Split a key into its parts. Keys in an unrecognized format are taken to be the URL as-is.
+    pub fn parse(key: &str) -> CacheKey {
+        let mut parsed = CacheKey {
+            url: key.to_string(),
+            upload_identifier: None,
+            top_frame_site: None,
+            frame_site: None,
+            subframe_document_resource: false,
+        };
+
+        // Keys that aren't single-keyed start with `<credentials>/<upload identifier>/`.
+        let mut rest = key;
+        let mut parts = key.splitn(3, '/');
+        if let (Some(credentials), Some(upload_identifier), Some(remainder)) =
+            (parts.next(), parts.next(), parts.next())
+        {
+            if let (Ok(_), Ok(upload_identifier)) =
+                (credentials.parse::<u32>(), upload_identifier.parse::<i64>())
+            {
+                parsed.upload_identifier = Some(upload_identifier);
+                rest = remainder;
+            }
+        }
+
+        if let Some(mut isolated) = rest.strip_prefix(DOUBLE_KEY_PREFIX) {
+            loop {
+                if let Some(stripped) = isolated.strip_prefix(SUBFRAME_DOCUMENT_RESOURCE_PREFIX) {
+                    parsed.subframe_document_resource = true;
+                    isolated = stripped;
+                } else if let Some(stripped) = isolated.strip_prefix(CREDENTIALLESS_PREFIX) {
+                    isolated = stripped;
+                } else {
+                    break;
+                }
+            }
+
+            // URLs can't contain spaces, so the URL is always the last part.
+            let mut sites = isolated.split(' ').collect::<Vec<_>>();
+            rest = sites.pop().unwrap_or_default();
+            parsed.top_frame_site = sites.first().map(|site| site.to_string());
+            parsed.frame_site = sites.get(1).map(|site| site.to_string());
+        }
+
+        parsed.url = rest.to_string();
+        parsed
+    }
+
+    /// The host of the URL, if it has one.
+    pub fn host(&self) -> Option<&str> {
+        let (_, rest) = self.url.split_once("://")?;
+        let authority = rest.split(['/', '?', '#']).next()?;
+        let host = authority
+            .rsplit_once('@')
+            .map_or(authority, |(_, host)| host);
+
+        // Strip the port, taking care not to split IPv6 literals.
+        let host = match host.strip_prefix('[') {
+            Some(ipv6) => ipv6.split_once(']').map_or(ipv6, |(ipv6, _)| ipv6),
+            None => host.split_once(':').map_or(host, |(host, _)| host),
+        };
+
+        (!host.is_empty()).then_some(host)
+    }
+}
+
+#[test]
+fn test_cache_key_parse() {
+    let key = CacheKey::parse("https://example.com/a.png");
+    assert_eq!(key.url, "https://example.com/a.png");
+    assert_eq!(key.upload_identifier, None);
+    assert_eq!(key.host(), Some("example.com"));
+
+    let key = CacheKey::parse(
+        "1/0/_dk_https://example.com https://example.com https://cdn.example.net:8443/a.js?v=1",
+    );
+    assert_eq!(key.url, "https://cdn.example.net:8443/a.js?v=1");
+    assert_eq!(key.upload_identifier, Some(0));
+    assert_eq!(key.top_frame_site.as_deref(), Some("https://example.com"));
+    assert_eq!(key.frame_site.as_deref(), Some("https://example.com"));
+    assert_eq!(key.host(), Some("cdn.example.net"));
+
+    let key = CacheKey::parse("1/0/_dk_s_https://a.test https://a.test http://[::1]:80/");
+    assert!(key.subframe_document_resource);
+    assert_eq!(key.url, "http://[::1]:80/");
+    assert_eq!(key.host(), Some("::1"));
+}
@@ -0,0 +1,44 @@
+//! Decoding of the `Content-Encoding` applied to cached response bodies. Chrome stores bodies as
+//! they were received, so compressed responses stay compressed in the cache.
+use std::io::Read;
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+
+use crate::{CCPError, CCPResult};
+
+const BROTLI_BUFFER_SIZE: usize = 4096;
+
+/// Whether every coding listed in a `Content-Encoding` header can be decoded.
+pub fn is_supported(content_encoding: &str) -> bool {
+    content_encoding.split(',').all(|coding| {
+        matches!(
+            coding.trim().to_ascii_lowercase().as_str(),
+            "" | "identity" | "gzip" | "x-gzip" | "deflate" | "br"
+        )
+    })
+}
+
+/// Wrap a reader of an encoded body in decoders for the codings listed in a `Content-Encoding`
+/// header (e.g. `gzip` or `deflate, br`). Codings are undone in the reverse of the order they were
+/// applied in.
+pub fn decoder<'a>(
+    reader: Box<dyn Read + 'a>,
+    content_encoding: &str,
+) -> CCPResult<Box<dyn Read + 'a>> {
+    let mut reader = reader;
+
+    for coding in content_encoding.rsplit(',').map(str::trim) {
+        reader = match coding.to_ascii_lowercase().as_str() {
+            "" | "identity" => reader,
+            "gzip" | "x-gzip" => Box::new(GzDecoder::new(reader)),
+            "deflate" => Box::new(ZlibDecoder::new(reader)),
+            "br" => Box::new(brotli_decompressor::Decompressor::new(
+                reader,
+                BROTLI_BUFFER_SIZE,
+            )),
+            _ => return Err(CCPError::UnsupportedEncoding(coding.to_string())),
+        };
+    }
+
+    Ok(reader)
+}
@@ -0,0 +1,259 @@
+//! Extraction of the cookies set by cached responses, which outlive the browser's cookie store
+//! when it's cleared without clearing the cache.
+//!
+//! See: https://www.rfc-editor.org/rfc/rfc6265#section-5.2
+use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
+
+use crate::{
+    freshness::parse_http_date, response_info::HttpHeaders, time::WindowsEpochMicroseconds,
+    CCPResult, CacheAddr, CacheKey, ChromeCache,
+};
+
+/// The `SameSite` attribute of a cookie.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+/// A cookie parsed from a `Set-Cookie` header.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SetCookie {
+    pub name: String,
+    pub value: String,
+    /// The `Domain` attribute, without a leading dot and lowercased.
+    pub domain: Option<String>,
+    pub path: Option<String>,
+    pub expires: Option<DateTime<Utc>>,
+    /// The `Max-Age` attribute in seconds, which takes precedence over `Expires`.
+    pub max_age: Option<i64>,
+    pub same_site: Option<SameSite>,
+    pub secure: bool,
+    pub http_only: bool,
+    pub partitioned: bool,
+}
+
+/// Parse a cookie date, in any of the formats allowed for HTTP dates or the `Wdy, DD-Mon-YYYY`
+/// format commonly used in `Expires`.
+fn parse_cookie_date(value: &str) -> Option<DateTime<Utc>> {
+    parse_http_date(value).or_else(|| {
+        NaiveDateTime::parse_from_str(value.trim(), "%a, %d-%b-%Y %H:%M:%S GMT")
+            .ok()
+            .map(|date| date.and_utc())
+    })
+}
+
+impl SetCookie {
+    /// Parse the value of a `Set-Cookie` header. Unknown and malformed attributes are ignored, as
+    /// browsers do. Returns `None` when the cookie has neither a name nor a value.
+    pub fn parse(header: &str) -> Option<SetCookie> {
+        let mut parts = header.split(';');
+        let pair = parts.next()?;
+        let (name, value) = match pair.split_once('=') {
+            Some((name, value)) => (name.trim(), value.trim()),
+            None => ("", pair.trim()),
+        };
+        if name.is_empty() && value.is_empty() {
+            return None;
+        }
+
+        let mut cookie = SetCookie {
+            name: name.to_string(),
+            value: value.to_string(),
+            domain: None,
+            path: None,
+            expires: None,
+            max_age: None,
+            same_site: None,
+            secure: false,
+            http_only: false,
+            partitioned: false,
+        };
+
+        for attribute in parts {
+            let (name, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+            let value = value.trim();
+
+            match name.trim().to_ascii_lowercase().as_str() {
+                "expires" => {
+                    if let Some(expires) = parse_cookie_date(value) {
+                        cookie.expires = Some(expires);
+                    }
+                }
+                "max-age" => {
+                    if let Ok(max_age) = value.parse() {
+                        cookie.max_age = Some(max_age);
+                    }
+                }
+                "domain" => {
+                    let domain = value.trim_start_matches('.').to_ascii_lowercase();
+                    if !domain.is_empty() {
+                        cookie.domain = Some(domain);
+                    }
+                }
+                "path" => cookie.path = value.starts_with('/').then(|| value.to_string()),
+                "samesite" => {
+                    cookie.same_site = match value.to_ascii_lowercase().as_str() {
+                        "strict" => Some(SameSite::Strict),
+                        "lax" => Some(SameSite::Lax),
+                        "none" => Some(SameSite::None),
+                        _ => None,
+                    }
+                }
+                "secure" => cookie.secure = true,
+                "httponly" => cookie.http_only = true,
+                "partitioned" => cookie.partitioned = true,
+                _ => {}
+            }
+        }
+
+        Some(cookie)
+    }
+
+    /// Every cookie set by the given response headers.
+    pub fn from_headers(headers: &HttpHeaders) -> Vec<SetCookie> {
+        headers
+            .get_all("set-cookie")
+            .filter_map(SetCookie::parse)
+            .collect()
+    }
+
+    /// When the cookie expires, for a response received at `response_time`. `None` for session
+    /// cookies. A `Max-Age` too large to represent expires at the latest representable date.
+    pub fn expiry(&self, response_time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self.max_age {
+            Some(max_age) => Some(
+                TimeDelta::try_seconds(max_age.max(0))
+                    .and_then(|max_age| response_time.checked_add_signed(max_age))
+                    .unwrap_or(DateTime::<Utc>::MAX_UTC),
+            ),
+            None => self.expires,
+        }
+    }
+}
+
+/// The default path of cookies set by a response for `url`: the directory of the URL's path.
+fn default_path(url: &str) -> String {
+    let path = url
+        .split_once("://")
+        .map_or(url, |(_, rest)| rest)
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default();
+    let path = path.find('/').map_or("", |start| &path[start..]);
+
+    match path.rfind('/') {
+        Some(end) if end > 0 => path[..end].to_string(),
+        _ => "/".to_string(),
+    }
+}
+
+/// A cookie set by a cached response.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CachedCookie {
+    /// The address of the entry holding the response.
+    pub address: CacheAddr,
+    /// The URL of the response.
+    pub url: String,
+    /// When the response was received, i.e. when the cookie was set.
+    pub response_time: WindowsEpochMicroseconds,
+    /// The domain the cookie applies to: its `Domain` attribute, or the URL's host for host-only
+    /// cookies.
+    pub domain: String,
+    pub host_only: bool,
+    /// The path the cookie applies to: its `Path` attribute, or the default for the URL.
+    pub path: String,
+    /// When the cookie expires, `None` for session cookies.
+    pub expires: Option<DateTime<Utc>>,
+    pub cookie: SetCookie,
+}
+
+impl CachedCookie {
+    /// Associate a cookie with the URL and response time of the response that set it.
+    pub fn new(
+        address: CacheAddr,
+        url: &str,
+        response_time: WindowsEpochMicroseconds,
+        cookie: SetCookie,
+    ) -> CachedCookie {
+        let host = CacheKey::parse(url).host().unwrap_or_default().to_string();
+        CachedCookie {
+            address,
+            url: url.to_string(),
+            response_time,
+            domain: cookie.domain.clone().unwrap_or(host),
+            host_only: cookie.domain.is_none(),
+            path: cookie.path.clone().unwrap_or_else(|| default_path(url)),
+            expires: response_time
+                .into_datetime_utc()
+                .ok()
+                .and_then(|time| cookie.expiry(time))
+                .or(cookie.expires),
+            cookie,
+        }
+    }
+}
+
+/// Every cookie set by the cached responses, in the order the responses were received. Entries
+/// without parseable response info are skipped.
+pub fn cached_cookies(cache: &ChromeCache) -> CCPResult<Vec<CachedCookie>> {
+    let mut cookies = Vec::new();
+
+    for entry in cache.entries()? {
+        let (Ok(key), Ok(info)) = (entry.key(), entry.response_info()) else {
+            continue;
+        };
+        let url = CacheKey::parse(&key).url;
+
+        for cookie in SetCookie::from_headers(&info.headers) {
+            cookies.push(CachedCookie::new(
+                entry.location().addr,
+                &url,
+                info.response_time,
+                cookie,
+            ));
+        }
+    }
+
+    cookies.sort_by_key(|cookie| cookie.response_time);
+    Ok(cookies)
+}
+
+#[test]
+fn test_set_cookie() {
+    let cookie = SetCookie::parse(
+        "session=abc123; Domain=.Example.com; Path=/; Secure; HttpOnly; SameSite=Lax; \
+         Expires=Wed, 21-Oct-2026 07:28:00 GMT",
+    )
+    .unwrap();
+    assert_eq!(cookie.name, "session");
+    assert_eq!(cookie.value, "abc123");
+    assert_eq!(cookie.domain.as_deref(), Some("example.com"));
+    assert_eq!(cookie.path.as_deref(), Some("/"));
+    assert_eq!(cookie.same_site, Some(SameSite::Lax));
+    assert!(cookie.secure && cookie.http_only && !cookie.partitioned);
+    assert_eq!(cookie.expires, DateTime::from_timestamp(1_792_567_680, 0));
+
+    let set_at = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    let max_age = SetCookie::parse("theme=dark; Max-Age=3600; Expires=0; Path=relative").unwrap();
+    assert_eq!(max_age.expiry(set_at), Some(set_at + TimeDelta::hours(1)));
+    assert_eq!(max_age.path, None);
+    assert_eq!(SetCookie::parse(" ; Secure"), None);
+    let huge_max_age = SetCookie::parse("forever=1; Max-Age=99999999999999999").unwrap();
+    assert_eq!(huge_max_age.expiry(set_at), Some(DateTime::<Utc>::MAX_UTC));
+
+    let cached = CachedCookie::new(
+        CacheAddr::from(0),
+        "https://www.example.com/account/login?next=/",
+        WindowsEpochMicroseconds::new(13_300_000_000_000_000),
+        max_age,
+    );
+    assert_eq!(cached.domain, "www.example.com");
+    assert!(cached.host_only);
+    assert_eq!(cached.path, "/account");
+    assert_eq!(default_path("https://example.com"), "/");
+}
@@ -0,0 +1,186 @@
+use std::fmt::{self, Display};
+
+use crate::{cache_address::FileType, time::WindowsEpochMicroseconds, CacheAddr};
+
+/// The number of raw bytes displayed per field before the rest is elided.
+const DISPLAYED_BYTES: usize = 16;
+
+/// A single field of a raw cache structure, as it's laid out on disk.
+#[derive(Debug, Clone)]
+pub struct FieldDescription {
+    pub name: String,
+    /// The absolute offset of the field within the file holding the structure.
+    pub offset: usize,
+    pub bytes: Vec<u8>,
+    pub value: String,
+}
+
+/// Every field of a raw cache structure, including padding and private fields.
+#[derive(Debug, Clone)]
+pub struct StructDescription {
+    pub name: &'static str,
+    pub offset: usize,
+    pub fields: Vec<FieldDescription>,
+}
+
+/// Raw cache structures that can be dumped field by field.
+pub trait Describe {
+    /// Describe the structure, given its absolute offset within the file holding it.
+    fn describe(&self, offset: usize) -> StructDescription;
+}
+
+impl Display for StructDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} @ 0x{:08x}", self.name, self.offset)?;
+
+        for field in &self.fields {
+            let mut bytes = field
+                .bytes
+                .iter()
+                .take(DISPLAYED_BYTES)
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<Vec<_>>()
+                .join(" ");
+            if field.bytes.len() > DISPLAYED_BYTES {
+                bytes.push_str(&format!(" .. ({} bytes)", field.bytes.len()));
+            }
+
+            writeln!(
+                f,
+                "  0x{:08x}  {:<24} {:<48} {}",
+                field.offset, field.name, bytes, field.value
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Walks the raw bytes of a structure field by field, in declaration order.
+pub(crate) struct FieldWriter<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    cursor: usize,
+    fields: Vec<FieldDescription>,
+}
+
+impl<'a> FieldWriter<'a> {
+    pub(crate) fn new(bytes: &'a [u8], offset: usize) -> FieldWriter<'a> {
+        FieldWriter {
+            bytes,
+            offset,
+            cursor: 0,
+            fields: Vec::new(),
+        }
+    }
+
+    /// Describe the next `size` bytes of the structure.
+    pub(crate) fn field(&mut self, name: &str, size: usize, value: impl Display) -> &mut Self {
+        self.fields.push(FieldDescription {
+            name: name.to_string(),
+            offset: self.offset + self.cursor,
+            bytes: self.bytes[self.cursor..self.cursor + size].to_vec(),
+            value: value.to_string(),
+        });
+        self.cursor += size;
+        self
+    }
+
+    pub(crate) fn int<T: Display>(&mut self, name: &str, value: T) -> &mut Self {
+        self.field(name, std::mem::size_of::<T>(), value)
+    }
+
+    pub(crate) fn hex(&mut self, name: &str, value: u32) -> &mut Self {
+        self.field(name, 4, format!("0x{:08x}", value))
+    }
+
+    pub(crate) fn ints<T: Display + Copy>(&mut self, name: &str, values: &[T]) -> &mut Self {
+        for (i, value) in values.iter().enumerate() {
+            self.int(&format!("{}[{}]", name, i), *value);
+        }
+        self
+    }
+
+    pub(crate) fn addr(&mut self, name: &str, addr: CacheAddr) -> &mut Self {
+        self.field(name, 4, describe_addr(addr))
+    }
+
+    pub(crate) fn addrs(&mut self, name: &str, addrs: &[CacheAddr]) -> &mut Self {
+        for (i, addr) in addrs.iter().enumerate() {
+            self.addr(&format!("{}[{}]", name, i), *addr);
+        }
+        self
+    }
+
+    pub(crate) fn time(&mut self, name: &str, time: WindowsEpochMicroseconds) -> &mut Self {
+        let value = match time.into_datetime_utc() {
+            Ok(date) => format!("{} ({})", time.as_micros(), date.to_rfc3339()),
+            Err(_) => time.as_micros().to_string(),
+        };
+        self.field(name, 8, value)
+    }
+
+    /// Describe the next `size` bytes without decoding them.
+    pub(crate) fn raw(&mut self, name: &str, size: usize) -> &mut Self {
+        self.field(name, size, "")
+    }
+
+    pub(crate) fn finish(&mut self, name: &'static str) -> StructDescription {
+        debug_assert_eq!(
+            self.cursor,
+            self.bytes.len(),
+            "{} not fully described",
+            name
+        );
+
+        StructDescription {
+            name,
+            offset: self.offset,
+            fields: std::mem::take(&mut self.fields),
+        }
+    }
+}
+
+fn describe_addr(addr: CacheAddr) -> String {
+    if !addr.is_initialized() {
+        return format!("0x{:08x} (uninitialized)", addr.value);
+    }
+
+    match addr.file_type() {
+        FileType::External => format!("0x{:08x} ({})", addr.value, addr.file_name()),
+        file_type => format!(
+            "0x{:08x} ({:?} {} block {} x{})",
+            addr.value,
+            file_type,
+            addr.file_name(),
+            addr.start_block(),
+            addr.num_blocks()
+        ),
+    }
+}
+
+#[test]
+fn test_describe_covers_every_byte() {
+    use crate::block_file::{BlockFileCacheEntry, BlockFileHeader, RankingsNode};
+    use crate::IndexHeader;
+    use zerocopy::FromZeroes;
+
+    let descriptions = [
+        IndexHeader::new_zeroed().describe(0),
+        BlockFileHeader::new_zeroed().describe(0),
+        BlockFileCacheEntry::new_zeroed().describe(0x2000),
+        RankingsNode::new_zeroed().describe(0x2024),
+    ];
+
+    for (description, size) in descriptions.iter().zip([368, 8192, 256, 36]) {
+        let last = description.fields.last().unwrap();
+        assert_eq!(last.offset + last.bytes.len(), description.offset + size);
+    }
+
+    let key = descriptions[2]
+        .fields
+        .iter()
+        .find(|f| f.name == "key")
+        .unwrap();
+    assert_eq!(key.offset, 0x2000 + 96);
+}
@@ -0,0 +1,360 @@
+//! Discovery of the caches of Chromium-based browsers and Electron apps under a filesystem root,
+//! e.g. a home directory or a mounted disk image, across the Windows, macOS and Linux layouts.
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use crate::cache_index::INDEX_MAGIC;
+
+/// The magic number at the start of the `index` file of a simple cache.
+const SIMPLE_INDEX_MAGIC: u64 = 0x656e74657220796f;
+
+/// The user data directories of each application, relative to a home directory. Browsers keep a
+/// profile directory per profile in theirs, while Electron apps and Opera use theirs as the
+/// profile directory. On macOS, the HTTP cache lives under `Library/Caches` rather than with the
+/// rest of the profile.
+const APPLICATIONS: &[(&str, &[&str])] = &[
+    (
+        "Chrome",
+        &[
+            "AppData/Local/Google/Chrome/User Data",
+            "AppData/Local/Google/Chrome Beta/User Data",
+            "AppData/Local/Google/Chrome SxS/User Data",
+            "Library/Application Support/Google/Chrome",
+            "Library/Caches/Google/Chrome",
+            ".config/google-chrome",
+            ".cache/google-chrome",
+            ".config/google-chrome-beta",
+            ".cache/google-chrome-beta",
+        ],
+    ),
+    (
+        "Chromium",
+        &[
+            "AppData/Local/Chromium/User Data",
+            "Library/Application Support/Chromium",
+            "Library/Caches/Chromium",
+            ".config/chromium",
+            ".cache/chromium",
+            "snap/chromium/common/chromium",
+            "snap/chromium/common/.cache/chromium",
+        ],
+    ),
+    (
+        "Edge",
+        &[
+            "AppData/Local/Microsoft/Edge/User Data",
+            "Library/Application Support/Microsoft Edge",
+            "Library/Caches/Microsoft Edge",
+            ".config/microsoft-edge",
+            ".cache/microsoft-edge",
+        ],
+    ),
+    (
+        "Brave",
+        &[
+            "AppData/Local/BraveSoftware/Brave-Browser/User Data",
+            "Library/Application Support/BraveSoftware/Brave-Browser",
+            "Library/Caches/BraveSoftware/Brave-Browser",
+            ".config/BraveSoftware/Brave-Browser",
+            ".cache/BraveSoftware/Brave-Browser",
+        ],
+    ),
+    (
+        "Opera",
+        &[
+            "AppData/Roaming/Opera Software/Opera Stable",
+            "AppData/Local/Opera Software/Opera Stable",
+            "Library/Application Support/com.operasoftware.Opera",
+            "Library/Caches/com.operasoftware.Opera",
+            ".config/opera",
+            ".cache/opera",
+        ],
+    ),
+    (
+        "Vivaldi",
+        &[
+            "AppData/Local/Vivaldi/User Data",
+            "Library/Application Support/Vivaldi",
+            "Library/Caches/Vivaldi",
+            ".config/vivaldi",
+            ".cache/vivaldi",
+        ],
+    ),
+    (
+        "Slack",
+        &[
+            "AppData/Roaming/Slack",
+            "Library/Application Support/Slack",
+            "Library/Containers/com.tinyspeck.slackmacgap/Data/Library/Application Support/Slack",
+            ".config/Slack",
+        ],
+    ),
+    (
+        "Discord",
+        &[
+            "AppData/Roaming/discord",
+            "Library/Application Support/discord",
+            ".config/discord",
+        ],
+    ),
+    (
+        "Teams",
+        &[
+            "AppData/Roaming/Microsoft/Teams",
+            "Library/Application Support/Microsoft/Teams",
+            ".config/Microsoft/Microsoft Teams",
+        ],
+    ),
+    (
+        "VS Code",
+        &[
+            "AppData/Roaming/Code",
+            "Library/Application Support/Code",
+            ".config/Code",
+        ],
+    ),
+];
+
+/// What a cache holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum CacheKind {
+    /// The HTTP cache, `Cache`.
+    Http,
+    /// Compiled JavaScript and WebAssembly, `Code Cache/js` and `Code Cache/wasm`.
+    Code,
+    /// Compiled GPU shaders, `GPUCache`.
+    Gpu,
+    /// The responses stored by service workers through the Cache API,
+    /// `Service Worker/CacheStorage`.
+    CacheStorage,
+}
+
+/// The on-disk format of a cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum CacheFormat {
+    /// An `index` file and block files, the format read by `ChromeCache`.
+    BlockFile,
+    /// The simple cache, with a file per entry, used on Linux, macOS and Android.
+    Simple,
+    /// A directory per origin holding an `index.txt` and a simple cache per named cache.
+    CacheStorage,
+    /// Empty or unrecognized.
+    Unknown,
+}
+
+impl CacheFormat {
+    /// Detect the format of the cache in `path`.
+    pub fn detect(path: &Path) -> CacheFormat {
+        let mut magic = [0; 8];
+        let read = fs::File::open(path.join("index"))
+            .and_then(|mut index| index.read(&mut magic))
+            .unwrap_or(0);
+
+        if read >= 4 && u32::from_le_bytes([magic[0], magic[1], magic[2], magic[3]]) == INDEX_MAGIC
+        {
+            CacheFormat::BlockFile
+        } else if (read == 8 && u64::from_le_bytes(magic) == SIMPLE_INDEX_MAGIC)
+            || path.join("index-dir").is_dir()
+        {
+            CacheFormat::Simple
+        } else if subdirectories(path)
+            .iter()
+            .any(|origin| origin.join("index.txt").is_file())
+        {
+            CacheFormat::CacheStorage
+        } else {
+            CacheFormat::Unknown
+        }
+    }
+}
+
+/// A cache found by `discover_caches`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DiscoveredCache {
+    pub application: &'static str,
+    /// The name of the profile directory, e.g. `Default` or `Profile 1`, or `None` for
+    /// applications without profiles.
+    pub profile: Option<String>,
+    pub kind: CacheKind,
+    pub format: CacheFormat,
+    pub path: PathBuf,
+}
+
+/// The subdirectories of `path`, sorted by name. Unreadable directories have none.
+fn subdirectories(path: &Path) -> Vec<PathBuf> {
+    let mut subdirectories = fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    subdirectories.sort();
+    subdirectories
+}
+
+/// The home directories under `root`: the root itself along with the user directories of a
+/// Windows or macOS (`Users`) or Linux (`home`, `root`) filesystem.
+fn home_directories(root: &Path) -> Vec<PathBuf> {
+    let mut homes = vec![root.to_path_buf()];
+    for users in ["Users", "home"] {
+        homes.extend(subdirectories(&root.join(users)));
+    }
+    homes.push(root.join("root"));
+    homes
+}
+
+/// The caches in a profile directory.
+fn profile_caches(profile: &Path) -> Vec<(CacheKind, PathBuf)> {
+    let mut caches = Vec::new();
+
+    // Since chrome 82 the HTTP cache is in a `Cache_Data` subdirectory.
+    let http = profile.join("Cache");
+    match http.join("Cache_Data") {
+        data if data.is_dir() => caches.push((CacheKind::Http, data)),
+        _ if http.is_dir() => caches.push((CacheKind::Http, http)),
+        _ => {}
+    }
+
+    let code = profile.join("Code Cache");
+    let code_caches = ["js", "wasm"]
+        .into_iter()
+        .map(|kind| code.join(kind))
+        .filter(|path| path.is_dir())
+        .collect::<Vec<_>>();
+    if !code_caches.is_empty() {
+        caches.extend(code_caches.into_iter().map(|path| (CacheKind::Code, path)));
+    } else if code.is_dir() {
+        caches.push((CacheKind::Code, code));
+    }
+
+    for (kind, path) in [
+        (CacheKind::Gpu, profile.join("GPUCache")),
+        (
+            CacheKind::CacheStorage,
+            profile.join("Service Worker/CacheStorage"),
+        ),
+    ] {
+        if path.is_dir() {
+            caches.push((kind, path));
+        }
+    }
+
+    caches
+}
+
+/// Find the caches of every Chromium-based browser and Electron app under `root`, which may be a
+/// home directory or the root of a Windows, macOS or Linux filesystem. Caches are listed by home
+/// directory, application and profile.
+pub fn discover_caches(root: &Path) -> Vec<DiscoveredCache> {
+    let mut caches = Vec::new();
+
+    for home in home_directories(root) {
+        for (application, user_data_dirs) in APPLICATIONS {
+            for user_data in user_data_dirs.iter().map(|dir| home.join(dir)) {
+                if !user_data.is_dir() {
+                    continue;
+                }
+
+                let profiles = std::iter::once((None, user_data.clone())).chain(
+                    subdirectories(&user_data).into_iter().map(|profile| {
+                        let name = profile.file_name().map(|name| name.to_string_lossy());
+                        (name.map(|name| name.into_owned()), profile)
+                    }),
+                );
+                for (profile, profile_dir) in profiles {
+                    for (kind, path) in profile_caches(&profile_dir) {
+                        if caches
+                            .iter()
+                            .any(|cache: &DiscoveredCache| cache.path == path)
+                        {
+                            continue;
+                        }
+                        caches.push(DiscoveredCache {
+                            application,
+                            profile: profile.clone(),
+                            kind,
+                            format: CacheFormat::detect(&path),
+                            path,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    caches
+}
+
+#[test]
+fn test_discover_caches() {
+    let root = std::env::temp_dir().join(format!("ccp-discover-{}", uuid::Uuid::new_v4()));
+    let chrome = root.join("Users/alice/AppData/Local/Google/Chrome/User Data/Default");
+    let slack = root.join("home/bob/.config/Slack");
+    for dir in [
+        chrome.join("Cache/Cache_Data"),
+        chrome.join("Code Cache/js/index-dir"),
+        chrome.join("Service Worker/CacheStorage/0a1b/6f2c"),
+        slack.join("GPUCache"),
+    ] {
+        fs::create_dir_all(dir).unwrap();
+    }
+    fs::write(
+        chrome.join("Cache/Cache_Data/index"),
+        INDEX_MAGIC.to_le_bytes(),
+    )
+    .unwrap();
+    fs::write(
+        chrome.join("Service Worker/CacheStorage/0a1b/index.txt"),
+        b"",
+    )
+    .unwrap();
+
+    let caches = discover_caches(&root);
+    fs::remove_dir_all(&root).unwrap();
+
+    let found = caches
+        .iter()
+        .map(|cache| {
+            (
+                cache.application,
+                cache.profile.as_deref(),
+                cache.kind,
+                cache.format,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        found,
+        [
+            (
+                "Chrome",
+                Some("Default"),
+                CacheKind::Http,
+                CacheFormat::BlockFile
+            ),
+            (
+                "Chrome",
+                Some("Default"),
+                CacheKind::Code,
+                CacheFormat::Simple
+            ),
+            (
+                "Chrome",
+                Some("Default"),
+                CacheKind::CacheStorage,
+                CacheFormat::CacheStorage
+            ),
+            ("Slack", None, CacheKind::Gpu, CacheFormat::Unknown),
+        ]
+    );
+    assert!(caches[0].path.ends_with("Default/Cache/Cache_Data"));
+}
@@ -4,7 +4,7 @@ pub type CCPResult<T> = Result<T, CCPError>;
 
 #[derive(Error, Debug)]
 pub enum CCPError {
-    #[error("io error")]
+    #[error("io error ({source})")]
     Io {
         #[from]
         source: std::io::Error,
@@ -23,4 +23,19 @@ pub enum CCPError {
     CacheLocationCouldNotBeDetermined(),
     #[error("invalid timestamp ({0})")]
     InvalidTimestamp(u64),
+    #[error("unsupported content encoding ({0})")]
+    UnsupportedEncoding(String),
+    #[error("invalid pattern ({source})")]
+    InvalidPattern {
+        #[from]
+        source: regex::Error,
+    },
+    #[error("invalid rule ({0})")]
+    InvalidRule(String),
+    #[cfg(feature = "sqlite")]
+    #[error("sqlite error ({source})")]
+    Sqlite {
+        #[from]
+        source: rusqlite::Error,
+    },
 }
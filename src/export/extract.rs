@@ -0,0 +1,617 @@
+//! Extraction of the cached bodies into a directory tree mirroring their URLs, e.g.
+//! `https://example.com/assets/app.js` into `out/example.com/assets/app.js`.
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{self, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    block_file::LazyBlockFileCacheEntry,
+    content_encoding,
+    hash::{super_fast_hash, DigestReader, Digests},
+    response_info::RemoteEndpoint,
+    sniff::{ContentKind, ContentTypes, SNIFF_LEN},
+    CCPError, CCPResult, CacheAddr, CacheKey, ChromeCache,
+};
+
+/// The name of the manifest written into the root of the output directory.
+pub const MANIFEST_FILE_NAME: &str = "manifest.tsv";
+/// The name of the hash manifest written into the root of the output directory.
+pub const HASH_MANIFEST_FILE_NAME: &str = "hashes.tsv";
+
+/// Path components longer than this are truncated, keeping a hash of the full component.
+const MAX_COMPONENT_LEN: usize = 100;
+/// Relative paths longer than this are replaced by a hash of the URL under `<host>/_hashed`.
+const MAX_PATH_LEN: usize = 200;
+
+const UNKNOWN_HOST_DIR: &str = "_unknown";
+const HASHED_DIR: &str = "_hashed";
+const INDEX_FILE_NAME: &str = "index";
+const FALLBACK_EXTENSION: &str = "bin";
+
+/// Options for the extraction.
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptions {
+    /// Write bodies as stored, without undoing their `Content-Encoding`.
+    pub raw: bool,
+}
+
+/// A body written by the extraction.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ExtractedFile {
+    /// The path of the file, relative to the output directory.
+    pub path: PathBuf,
+    pub address: CacheAddr,
+    pub key: String,
+    /// The number of bytes written.
+    pub size: u64,
+    pub content_type: Option<String>,
+    /// The type sniffed from the first bytes of the written file, when it wasn't left encoded.
+    pub sniffed_type: Option<ContentKind>,
+    /// The `Content-Encoding` still applied to the written file, if it wasn't (or couldn't be)
+    /// decoded.
+    pub content_encoding: Option<String>,
+    /// The endpoint the response was received from.
+    pub remote_endpoint: Option<RemoteEndpoint>,
+    /// The digests of the written file.
+    pub digests: Digests,
+}
+
+/// The digests of a data stream of an entry, as stored in the cache.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StreamDigests {
+    pub address: CacheAddr,
+    pub stream: usize,
+    pub digests: Digests,
+}
+
+/// The digests of a file of the cache itself.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SourceDigests {
+    pub path: PathBuf,
+    pub digests: Digests,
+}
+
+/// The outcome of an extraction.
+#[derive(Debug, Default)]
+pub struct ExtractReport {
+    pub files: Vec<ExtractedFile>,
+    /// The digests of every non-empty stream of the entries, including those that failed to
+    /// extract.
+    pub streams: Vec<StreamDigests>,
+    /// The digests of the files of the cache, taken before anything was extracted.
+    pub sources_before: Vec<SourceDigests>,
+    /// The digests of the files of the cache, taken after everything was extracted.
+    pub sources_after: Vec<SourceDigests>,
+    /// Entries that couldn't be extracted, along with the reason.
+    pub failures: Vec<(CacheAddr, CCPError)>,
+}
+
+impl ExtractReport {
+    /// The files of the cache whose digests differ between before and after the extraction, or
+    /// that only exist at one of those times.
+    pub fn changed_sources(&self) -> Vec<&Path> {
+        let mut changed = Vec::new();
+
+        for before in &self.sources_before {
+            let after = self
+                .sources_after
+                .iter()
+                .find(|after| after.path == before.path);
+            if after.is_none_or(|after| after.digests != before.digests) {
+                changed.push(before.path.as_path());
+            }
+        }
+        for after in &self.sources_after {
+            if !self
+                .sources_before
+                .iter()
+                .any(|before| before.path == after.path)
+            {
+                changed.push(after.path.as_path());
+            }
+        }
+
+        changed
+    }
+}
+
+/// Replace the characters that aren't allowed in file names on common platforms.
+fn sanitize(component: &str) -> String {
+    let sanitized = component
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>();
+    let sanitized = sanitized.trim_end_matches(['.', ' ']);
+
+    match sanitized {
+        "" => "_".to_string(),
+        sanitized => sanitized.to_string(),
+    }
+}
+
+/// Truncate an over-long component, keeping its extension and a hash of the full component so
+/// that distinct components stay distinct.
+fn shorten(component: String) -> String {
+    if component.len() <= MAX_COMPONENT_LEN {
+        return component;
+    }
+
+    let hash = format!("~{:08x}", super_fast_hash(component.as_bytes()));
+    let extension = match component.rsplit_once('.') {
+        Some((_, extension)) if extension.len() <= 8 => format!(".{}", extension),
+        _ => String::new(),
+    };
+
+    let mut end = MAX_COMPONENT_LEN - hash.len() - extension.len();
+    while !component.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{}{}", &component[..end], hash, extension)
+}
+
+/// Split a file name into its stem and extension, if it has one.
+fn split_extension(file_name: &str) -> (&str, Option<&str>) {
+    match file_name.rsplit_once('.') {
+        Some((stem, extension))
+            if !stem.is_empty()
+                && !extension.is_empty()
+                && extension.len() <= 8
+                && extension.chars().all(|c| c.is_ascii_alphanumeric()) =>
+        {
+            (stem, Some(extension))
+        }
+        _ => (file_name, None),
+    }
+}
+
+/// The path a body is extracted to, relative to the output directory. URLs that only differ by
+/// query string get the hash of the query appended to the file name.
+fn relative_path(key: &CacheKey, extension: Option<&str>) -> PathBuf {
+    let host = key.host().map_or(UNKNOWN_HOST_DIR.to_string(), sanitize);
+
+    let rest = key.url.split_once("://").map_or("", |(_, rest)| rest);
+    let rest = rest.split_once('#').map_or(rest, |(rest, _)| rest);
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let path = path.split_once('/').map_or("", |(_, path)| path);
+
+    let mut segments = path.split('/').collect::<Vec<_>>();
+    let file_name = match segments.pop() {
+        Some(file_name) if !file_name.is_empty() => file_name,
+        _ => INDEX_FILE_NAME,
+    };
+
+    let (stem, existing_extension) = split_extension(file_name);
+    let stem = match query {
+        "" => stem.to_string(),
+        query => format!("{}_{:08x}", stem, super_fast_hash(query.as_bytes())),
+    };
+    let extension = existing_extension
+        .or(extension)
+        .unwrap_or(FALLBACK_EXTENSION);
+
+    let mut relative = PathBuf::from(&host);
+    for segment in segments.iter().filter(|segment| !segment.is_empty()) {
+        relative.push(shorten(sanitize(segment)));
+    }
+    relative.push(shorten(sanitize(&format!("{}.{}", stem, extension))));
+
+    if relative.as_os_str().len() > MAX_PATH_LEN {
+        hashed_path(key, &host, extension)
+    } else {
+        relative
+    }
+}
+
+fn hashed_path(key: &CacheKey, host: &str, extension: &str) -> PathBuf {
+    [
+        host.to_string(),
+        HASHED_DIR.to_string(),
+        format!("{:08x}.{}", super_fast_hash(key.url.as_bytes()), extension),
+    ]
+    .iter()
+    .collect()
+}
+
+/// Append `~N` to the file name, before the extension.
+fn with_suffix(path: &Path, n: usize) -> PathBuf {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let (stem, extension) = split_extension(&file_name);
+    let file_name = match extension {
+        Some(extension) => format!("{}~{}.{}", stem, n, extension),
+        None => format!("{}~{}", stem, n),
+    };
+    path.with_file_name(file_name)
+}
+
+/// Create the file for a body, numbering it when the path is taken (by an earlier entry with the
+/// same URL, or a directory of another entry) and falling back to a hashed path when a parent
+/// directory can't be created because a file is in the way.
+fn create_file(
+    out: &Path,
+    relative: PathBuf,
+    fallback: PathBuf,
+    taken: &mut HashSet<PathBuf>,
+) -> CCPResult<(PathBuf, File)> {
+    let relative = match out.join(&relative).parent().map(fs::create_dir_all) {
+        Some(Err(_)) => {
+            fs::create_dir_all(out.join(&fallback).parent().unwrap_or(out))?;
+            fallback
+        }
+        _ => relative,
+    };
+
+    let mut candidate = relative.clone();
+    let mut n = 0;
+    while taken.contains(&candidate) || out.join(&candidate).exists() {
+        n += 1;
+        candidate = with_suffix(&relative, n);
+    }
+
+    let file = File::create(out.join(&candidate))?;
+    taken.insert(candidate.clone());
+    Ok((candidate, file))
+}
+
+/// Write the sniffed prefix of a body and the rest of it, returning the number of bytes written.
+fn write_body(file: File, prefix: &[u8], reader: &mut impl Read) -> CCPResult<u64> {
+    let mut writer = BufWriter::new(file);
+    writer.write_all(prefix)?;
+    let size = prefix.len() as u64 + io::copy(reader, &mut writer)?;
+    writer.flush()?;
+    Ok(size)
+}
+
+/// Digest every file in the cache directory, in name order.
+fn source_digests(cache: &ChromeCache) -> CCPResult<Vec<SourceDigests>> {
+    let mut paths = fs::read_dir(cache.path())?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.retain(|path| path.is_file());
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let digests = Digests::of_file(&path)?;
+            Ok(SourceDigests { path, digests })
+        })
+        .collect()
+}
+
+/// Extract the body of an entry, digesting each of its streams as they're read. The digests of
+/// every stream that can be read are kept, even when the entry fails to extract.
+fn extract_entry(
+    entry: &LazyBlockFileCacheEntry,
+    out: &Path,
+    options: &ExtractOptions,
+    taken: &mut HashSet<PathBuf>,
+    streams: &mut Vec<StreamDigests>,
+) -> CCPResult<Option<ExtractedFile>> {
+    let key = entry.key()?;
+    let address = entry.location().addr;
+    let sizes = entry.get()?.data_size;
+    let digest = |stream| {
+        entry
+            .stream_reader(stream)
+            .and_then(|reader| Ok(Digests::of_reader(reader)?))
+    };
+
+    let mut entry_streams = Vec::new();
+    let mut error = None;
+    for (stream, _) in sizes
+        .iter()
+        .enumerate()
+        .filter(|(stream, size)| *stream != 1 && **size > 0)
+    {
+        match digest(stream) {
+            Ok(digests) => entry_streams.push(StreamDigests {
+                address,
+                stream,
+                digests,
+            }),
+            Err(err) => {
+                error.get_or_insert(err);
+            }
+        }
+    }
+
+    let result = match error {
+        Some(err) => Err(err),
+        None if sizes[1] <= 0 => Ok(None),
+        None => extract_body(entry, key, out, options, taken, &mut entry_streams).map(Some),
+    };
+
+    // A body that couldn't be extracted is digested again as stored, for the hash manifest.
+    if sizes[1] > 0 && !entry_streams.iter().any(|stream| stream.stream == 1) {
+        if let Ok(digests) = digest(1) {
+            entry_streams.push(StreamDigests {
+                address,
+                stream: 1,
+                digests,
+            });
+        }
+    }
+    entry_streams.sort_by_key(|stream| stream.stream);
+    streams.append(&mut entry_streams);
+
+    result
+}
+
+/// Write the body of an entry into the output directory, adding the digests of the stored body to
+/// `entry_streams` once it's been read to the end.
+fn extract_body(
+    entry: &LazyBlockFileCacheEntry,
+    key: String,
+    out: &Path,
+    options: &ExtractOptions,
+    taken: &mut HashSet<PathBuf>,
+    entry_streams: &mut Vec<StreamDigests>,
+) -> CCPResult<ExtractedFile> {
+    let address = entry.location().addr;
+    let info = entry.response_info()?;
+
+    let content_type = info.headers.get("content-type").map(str::to_string);
+    let mut content_encoding = info
+        .headers
+        .get("content-encoding")
+        .filter(|encoding| !encoding.eq_ignore_ascii_case("identity"))
+        .map(str::to_string);
+
+    // The stored body is digested beneath the decoder and the written file above it.
+    let mut stored = DigestReader::new(entry.stream_reader(1)?);
+    let decode = content_encoding
+        .clone()
+        .filter(|encoding| !options.raw && content_encoding::is_supported(encoding));
+    let reader: Box<dyn Read + '_> = match decode {
+        Some(encoding) => {
+            content_encoding = None;
+            content_encoding::decoder(Box::new(&mut stored), &encoding)?
+        }
+        None => Box::new(&mut stored),
+    };
+    let mut reader = DigestReader::new(reader);
+
+    let mut prefix = Vec::new();
+    reader
+        .by_ref()
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut prefix)?;
+    let types = match content_encoding {
+        Some(_) => ContentTypes::new(content_type.as_deref(), &[]),
+        None => ContentTypes::new(content_type.as_deref(), &prefix),
+    };
+    let extension = match content_encoding {
+        Some(_) => None,
+        None => types.best_kind().map(|kind| kind.extension()),
+    };
+
+    let parsed_key = CacheKey::parse(&key);
+    let relative = relative_path(&parsed_key, extension);
+    let fallback = hashed_path(
+        &parsed_key,
+        &parsed_key
+            .host()
+            .map_or(UNKNOWN_HOST_DIR.to_string(), sanitize),
+        extension.unwrap_or(FALLBACK_EXTENSION),
+    );
+    let (path, file) = create_file(out, relative, fallback, taken)?;
+
+    let written = write_body(file, &prefix, &mut reader);
+    let digests = reader.finish();
+    // Decoders may stop before the end of the stored body, e.g. at trailing garbage.
+    let written = written.and_then(|size| {
+        io::copy(&mut stored, &mut io::sink())?;
+        Ok(size)
+    });
+    // A body that fails part way isn't left behind without a row in the manifest.
+    let size = match written {
+        Ok(size) => size,
+        Err(err) => {
+            let _ = fs::remove_file(out.join(&path));
+            taken.remove(&path);
+            return Err(err);
+        }
+    };
+    entry_streams.push(StreamDigests {
+        address,
+        stream: 1,
+        digests: stored.finish(),
+    });
+
+    Ok(ExtractedFile {
+        path,
+        address,
+        key,
+        size,
+        content_type,
+        sniffed_type: types.sniffed,
+        content_encoding,
+        remote_endpoint: info.remote_endpoint,
+        digests,
+    })
+}
+
+/// Write the manifest mapping each extracted file back to the entry it came from, as tab
+/// separated values with a header row.
+pub fn write_manifest<W: Write>(files: &[ExtractedFile], mut writer: W) -> CCPResult<()> {
+    writeln!(
+        writer,
+        "path\taddress\tsize\tcontent_type\tsniffed_type\tcontent_encoding\tremote_endpoint\tkey"
+    )?;
+
+    for file in files {
+        writeln!(
+            writer,
+            "{}\t0x{:08x}\t{}\t{}\t{}\t{}\t{}\t{}",
+            file.path.display(),
+            file.address.value,
+            file.size,
+            file.content_type.as_deref().unwrap_or_default(),
+            file.sniffed_type
+                .map(|kind| kind.mime_type())
+                .unwrap_or_default(),
+            file.content_encoding.as_deref().unwrap_or_default(),
+            file.remote_endpoint
+                .as_ref()
+                .map(|endpoint| endpoint.to_string())
+                .unwrap_or_default(),
+            file.key
+        )?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write the hash manifest of an extraction, as tab separated values with a header row: the
+/// digests of the cache's files before extracting, of every stream as stored, of every extracted
+/// file and of the cache's files after extracting, in that order.
+pub fn write_hash_manifest<W: Write>(report: &ExtractReport, mut writer: W) -> CCPResult<()> {
+    writeln!(
+        writer,
+        "kind\tpath\taddress\tstream\tsize\tmd5\tsha1\tsha256"
+    )?;
+
+    let mut row = |kind: &str, path: String, address: String, stream: String, digests: &Digests| {
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            kind, path, address, stream, digests.size, digests.md5, digests.sha1, digests.sha256
+        )
+    };
+
+    for source in &report.sources_before {
+        let path = source.path.display().to_string();
+        row(
+            "source_before",
+            path,
+            "-".into(),
+            "-".into(),
+            &source.digests,
+        )?;
+    }
+    for stream in &report.streams {
+        let address = format!("0x{:08x}", stream.address.value);
+        row(
+            "stream",
+            "-".into(),
+            address,
+            stream.stream.to_string(),
+            &stream.digests,
+        )?;
+    }
+    for file in &report.files {
+        let address = format!("0x{:08x}", file.address.value);
+        let path = file.path.display().to_string();
+        row("file", path, address, "1".into(), &file.digests)?;
+    }
+    for source in &report.sources_after {
+        let path = source.path.display().to_string();
+        row(
+            "source_after",
+            path,
+            "-".into(),
+            "-".into(),
+            &source.digests,
+        )?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write the body of every entry into `out/<host>/<path>`, decoded from its `Content-Encoding`
+/// unless `options.raw` is set, along with a manifest mapping the files back to their entries.
+///
+/// Files without an extension in their URL get one from their `Content-Type` or, failing that,
+/// their first bytes. Paths that are already taken are numbered rather than overwritten, and
+/// over-long paths are replaced by a hash of the URL. Entries without a body are skipped.
+///
+/// For chain of custody, the MD5, SHA-1 and SHA-256 digests of every stream and extracted file
+/// are computed as they're read and written, and of the cache's own files before and after
+/// extracting, and written to a hash manifest.
+pub fn extract(
+    cache: &ChromeCache,
+    out: &Path,
+    options: &ExtractOptions,
+) -> CCPResult<ExtractReport> {
+    fs::create_dir_all(out)?;
+
+    let mut report = ExtractReport {
+        sources_before: source_digests(cache)?,
+        ..Default::default()
+    };
+    let mut taken = HashSet::from([
+        PathBuf::from(MANIFEST_FILE_NAME),
+        PathBuf::from(HASH_MANIFEST_FILE_NAME),
+    ]);
+
+    for entry in cache.entries()? {
+        match extract_entry(&entry, out, options, &mut taken, &mut report.streams) {
+            Ok(Some(file)) => report.files.push(file),
+            Ok(None) => {}
+            Err(err) => report.failures.push((entry.location().addr, err)),
+        }
+    }
+
+    let manifest = BufWriter::new(File::create(out.join(MANIFEST_FILE_NAME))?);
+    write_manifest(&report.files, manifest)?;
+
+    report.sources_after = source_digests(cache)?;
+    let hash_manifest = BufWriter::new(File::create(out.join(HASH_MANIFEST_FILE_NAME))?);
+    write_hash_manifest(&report, hash_manifest)?;
+
+    Ok(report)
+}
+
+#[test]
+fn test_relative_path() {
+    let path = |url: &str, extension| {
+        relative_path(&CacheKey::parse(url), extension)
+            .to_string_lossy()
+            .replace('\\', "/")
+    };
+
+    assert_eq!(
+        path("https://example.com/", Some("html")),
+        "example.com/index.html"
+    );
+    assert_eq!(
+        path("https://example.com/a/b.js", Some("html")),
+        "example.com/a/b.js"
+    );
+    assert_eq!(
+        path("https://example.com/a/logo", Some("png")),
+        "example.com/a/logo.png"
+    );
+    assert_eq!(
+        path("https://example.com/a/blob", None),
+        "example.com/a/blob.bin"
+    );
+    assert_ne!(
+        path("https://example.com/a.js?v=1", None),
+        path("https://example.com/a.js?v=2", None)
+    );
+    assert_eq!(
+        path("https://example.com/../x:y", None),
+        "example.com/_/x_y.bin"
+    );
+
+    let long = path(&format!("https://example.com/{}.js", "a".repeat(150)), None);
+    assert!(long.len() < 120 && long.ends_with(".js"));
+    let deep = path(
+        &format!("https://example.com/{}x.js", "abcdefghij/".repeat(30)),
+        None,
+    );
+    assert!(deep.starts_with("example.com/_hashed/") && deep.ends_with(".js"));
+}
@@ -0,0 +1,246 @@
+//! Export of the cached responses as an HTTP Archive (HAR 1.2), for viewing in browser developer
+//! tools.
+//!
+//! See: http://www.softwareishard.com/blog/har-12-spec/
+use std::io::{self, Read, Write};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::SecondsFormat;
+use serde::Serialize;
+
+use crate::{
+    block_file::LazyBlockFileCacheEntry, time::WindowsEpochMicroseconds, CCPResult, CacheKey,
+    ChromeCache,
+};
+
+const HAR_VERSION: &str = "1.2";
+
+#[derive(Serialize)]
+struct Har {
+    log: Log,
+}
+
+#[derive(Serialize)]
+struct Log {
+    version: &'static str,
+    creator: Creator,
+    entries: Vec<Entry>,
+}
+
+#[derive(Serialize)]
+struct Creator {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    started_date_time: String,
+    time: f64,
+    request: Request,
+    response: Response,
+    cache: Cache,
+    timings: Timings,
+    #[serde(rename = "serverIPAddress", skip_serializing_if = "Option::is_none")]
+    server_ip_address: Option<String>,
+    #[serde(rename = "_cacheKey")]
+    cache_key: String,
+    #[serde(rename = "_cacheAddress")]
+    cache_address: String,
+}
+
+#[derive(Serialize)]
+struct NameValue {
+    name: String,
+    value: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Request {
+    method: &'static str,
+    url: String,
+    http_version: String,
+    cookies: Vec<NameValue>,
+    headers: Vec<NameValue>,
+    query_string: Vec<NameValue>,
+    headers_size: i64,
+    body_size: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Response {
+    status: u16,
+    status_text: String,
+    http_version: String,
+    cookies: Vec<NameValue>,
+    headers: Vec<NameValue>,
+    content: Content,
+    #[serde(rename = "redirectURL")]
+    redirect_url: String,
+    headers_size: i64,
+    body_size: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Content {
+    size: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compression: Option<i64>,
+    mime_type: String,
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encoding: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+struct Cache {}
+
+#[derive(Serialize)]
+struct Timings {
+    send: f64,
+    wait: f64,
+    receive: f64,
+}
+
+fn to_iso8601(time: WindowsEpochMicroseconds) -> String {
+    time.into_datetime_utc()
+        .map(|time| time.to_rfc3339_opts(SecondsFormat::Millis, true))
+        .unwrap_or_default()
+}
+
+fn query_string(url: &str) -> Vec<NameValue> {
+    let Some((_, query)) = url.split_once('?') else {
+        return Vec::new();
+    };
+    let query = query.split_once('#').map_or(query, |(query, _)| query);
+
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            NameValue {
+                name: name.to_string(),
+                value: value.to_string(),
+            }
+        })
+        .collect()
+}
+
+fn read_all(reader: CCPResult<Box<dyn Read>>) -> CCPResult<Vec<u8>> {
+    let mut data = Vec::new();
+    reader?.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+fn har_entry(entry: &LazyBlockFileCacheEntry) -> CCPResult<Entry> {
+    let key = entry.key()?;
+    let url = CacheKey::parse(&key).url;
+    let info = entry.response_info()?;
+    let headers = &info.headers;
+
+    // The body is given decoded, falling back to the stored bytes when it can't be decoded.
+    let encoded = read_all(entry.stream_reader(1))?;
+    let decoded = read_all(entry.decoded_body_reader()).unwrap_or_else(|_| encoded.clone());
+    let (text, encoding) = match String::from_utf8(decoded.clone()) {
+        Ok(text) => (text, None),
+        Err(_) => (STANDARD.encode(&decoded), Some("base64")),
+    };
+
+    let mut status_line = headers.status_line.splitn(3, ' ');
+    let http_version = status_line.next().unwrap_or_default().to_string();
+    let status_text = status_line.nth(1).unwrap_or_default().to_string();
+
+    let time = info
+        .response_time
+        .as_micros()
+        .saturating_sub(info.request_time.as_micros()) as f64
+        / 1000.0;
+
+    Ok(Entry {
+        started_date_time: to_iso8601(info.request_time),
+        time,
+        request: Request {
+            method: "GET",
+            query_string: query_string(&url),
+            url,
+            http_version: http_version.clone(),
+            cookies: Vec::new(),
+            headers: Vec::new(),
+            headers_size: -1,
+            body_size: -1,
+        },
+        response: Response {
+            status: headers.status_code().unwrap_or_default(),
+            status_text,
+            http_version,
+            cookies: Vec::new(),
+            headers: headers
+                .headers
+                .iter()
+                .map(|(name, value)| NameValue {
+                    name: name.clone(),
+                    value: value.clone(),
+                })
+                .collect(),
+            content: Content {
+                size: decoded.len() as i64,
+                compression: (decoded.len() != encoded.len())
+                    .then_some(decoded.len() as i64 - encoded.len() as i64),
+                mime_type: headers.get("content-type").unwrap_or_default().to_string(),
+                text,
+                encoding,
+            },
+            redirect_url: headers.get("location").unwrap_or_default().to_string(),
+            headers_size: -1,
+            body_size: encoded.len() as i64,
+        },
+        cache: Cache {},
+        timings: Timings {
+            send: 0.0,
+            wait: time,
+            receive: 0.0,
+        },
+        server_ip_address: info
+            .remote_endpoint
+            .and_then(|endpoint| endpoint.ip())
+            .map(|ip| ip.to_string()),
+        cache_key: key,
+        cache_address: format!("0x{:08x}", entry.location().addr.value),
+    })
+}
+
+/// Write the cached responses as a HAR log, one HAR entry per cache entry. Entries are sorted by
+/// request time, and those without parseable response info are skipped. Returns the number of
+/// entries written.
+///
+/// Chrome doesn't cache request headers, so requests are recorded as a `GET` of the entry's URL
+/// without headers.
+pub fn write_har<W: Write>(cache: &ChromeCache, mut writer: W) -> CCPResult<usize> {
+    let mut entries = cache
+        .entries()?
+        .filter_map(|entry| har_entry(&entry).ok())
+        .collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.started_date_time.cmp(&b.started_date_time));
+    let written = entries.len();
+
+    let har = Har {
+        log: Log {
+            version: HAR_VERSION,
+            creator: Creator {
+                name: env!("CARGO_PKG_NAME"),
+                version: env!("CARGO_PKG_VERSION"),
+            },
+            entries,
+        },
+    };
+
+    serde_json::to_writer_pretty(&mut writer, &har).map_err(io::Error::from)?;
+    writer.write_all(b"\n")?;
+    writer.flush()?;
+    Ok(written)
+}
@@ -0,0 +1,22 @@
+use std::io::{self, Write};
+
+use crate::{export::EntryMetadata, CCPResult, ChromeCache};
+
+/// Write the metadata of every entry in the cache as JSON Lines, one object per entry. Entries that
+/// can't be parsed are skipped. Returns the number of entries written.
+pub fn write_jsonl<W: Write>(cache: &ChromeCache, mut writer: W) -> CCPResult<usize> {
+    let mut written = 0;
+
+    for mut entry in cache.entries()? {
+        let Ok(metadata) = EntryMetadata::from_entry(&mut entry) else {
+            continue;
+        };
+
+        serde_json::to_writer(&mut writer, &metadata).map_err(io::Error::from)?;
+        writer.write_all(b"\n")?;
+        written += 1;
+    }
+
+    writer.flush()?;
+    Ok(written)
+}
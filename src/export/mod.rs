@@ -0,0 +1,71 @@
+//! Exporters that write the entries of a cache out in other formats.
+pub mod extract;
+#[cfg(feature = "serde")]
+pub mod har;
+#[cfg(feature = "serde")]
+pub mod jsonl;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod timeline;
+pub mod warc;
+
+use crate::{
+    block_file::{BlockCacheEntryState, LazyBlockFileCacheEntry},
+    response_info::RemoteEndpoint,
+    time::WindowsEpochMicroseconds,
+    CCPResult, CacheAddr,
+};
+
+/// The metadata of a cache entry shared by the exporters, gathered from the entry itself, its
+/// rankings node and its response info.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct EntryMetadata {
+    pub address: CacheAddr,
+    pub key: String,
+    pub hash: u32,
+    pub state: BlockCacheEntryState,
+    pub creation_time: WindowsEpochMicroseconds,
+    pub last_used: Option<WindowsEpochMicroseconds>,
+    pub last_modified: Option<WindowsEpochMicroseconds>,
+    pub reuse_count: i32,
+    pub refetch_count: i32,
+    pub stream_sizes: [i32; 4],
+    /// The endpoint the cached response was received from.
+    pub remote_endpoint: Option<RemoteEndpoint>,
+}
+
+impl EntryMetadata {
+    /// Gather the metadata of an entry. The rankings node times are left empty when the node
+    /// can't be read, as is the remote endpoint when the response info can't be parsed.
+    pub fn from_entry(entry: &mut LazyBlockFileCacheEntry) -> CCPResult<EntryMetadata> {
+        let key = entry.key()?;
+        let (last_used, last_modified) = match entry.get_rankings_node() {
+            Ok(node) => match node.get() {
+                Ok(node) => (Some(node.last_used), Some(node.last_modified)),
+                Err(_) => (None, None),
+            },
+            Err(_) => (None, None),
+        };
+
+        let remote_endpoint = entry
+            .response_info()
+            .ok()
+            .and_then(|info| info.remote_endpoint);
+
+        let parsed = entry.get()?;
+        Ok(EntryMetadata {
+            address: entry.location().addr,
+            key,
+            hash: parsed.hash,
+            state: parsed.state.kind(),
+            creation_time: parsed.creation_time,
+            last_used,
+            last_modified,
+            reuse_count: parsed.reuse_count,
+            refetch_count: parsed.refetch_count,
+            stream_sizes: parsed.data_size,
+            remote_endpoint,
+        })
+    }
+}
@@ -0,0 +1,171 @@
+use std::{io::Read, path::Path};
+
+use rusqlite::{params, Connection};
+
+use crate::{export::EntryMetadata, time::WindowsEpochMicroseconds, CCPResult, ChromeCache};
+
+const SCHEMA: &str = "
+CREATE TABLE entries (
+    id INTEGER PRIMARY KEY,
+    address INTEGER NOT NULL,
+    hash INTEGER NOT NULL,
+    key TEXT NOT NULL,
+    state TEXT NOT NULL,
+    creation_time TEXT,
+    last_used TEXT,
+    last_modified TEXT,
+    reuse_count INTEGER NOT NULL,
+    refetch_count INTEGER NOT NULL,
+    request_time TEXT,
+    response_time TEXT,
+    status_line TEXT,
+    status_code INTEGER,
+    remote_host TEXT,
+    remote_port INTEGER
+);
+CREATE TABLE response_headers (
+    entry_id INTEGER NOT NULL REFERENCES entries(id),
+    position INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    value TEXT NOT NULL
+);
+CREATE TABLE streams (
+    entry_id INTEGER NOT NULL REFERENCES entries(id),
+    stream INTEGER NOT NULL,
+    size INTEGER NOT NULL,
+    address INTEGER NOT NULL,
+    file TEXT,
+    offset INTEGER
+);
+CREATE TABLE bodies (
+    entry_id INTEGER NOT NULL REFERENCES entries(id),
+    stream INTEGER NOT NULL,
+    data BLOB NOT NULL
+);
+CREATE INDEX entries_key ON entries(key);
+CREATE INDEX response_headers_entry ON response_headers(entry_id);
+CREATE INDEX response_headers_name ON response_headers(name COLLATE NOCASE);
+CREATE INDEX streams_entry ON streams(entry_id);
+CREATE INDEX bodies_entry ON bodies(entry_id);
+";
+
+/// Options for the SQLite export.
+#[derive(Debug, Clone, Default)]
+pub struct SqliteExportOptions {
+    /// Store the contents of every stream after the first (i.e., the response bodies) in the
+    /// `bodies` table.
+    pub include_bodies: bool,
+}
+
+fn to_rfc3339(time: WindowsEpochMicroseconds) -> Option<String> {
+    time.into_datetime_utc().ok().map(|time| time.to_rfc3339())
+}
+
+/// Write the entries of the cache into a new SQLite database at `path`, with one row per entry in
+/// `entries` and their response headers, stream metadata and (optionally) bodies in the
+/// `response_headers`, `streams` and `bodies` tables. Entries that can't be parsed are skipped, as
+/// are bodies that can't be read.
+/// Returns the number of entries written.
+pub fn export_sqlite(
+    cache: &ChromeCache,
+    path: &Path,
+    options: &SqliteExportOptions,
+) -> CCPResult<usize> {
+    let mut connection = Connection::open(path)?;
+    connection.execute_batch(SCHEMA)?;
+
+    let transaction = connection.transaction()?;
+    let mut written = 0;
+
+    for mut entry in cache.entries()? {
+        let Ok(metadata) = EntryMetadata::from_entry(&mut entry) else {
+            continue;
+        };
+        let (Ok(parsed), Ok(locations)) = (entry.get(), entry.stream_locations()) else {
+            continue;
+        };
+        let response_info = entry.response_info().ok();
+
+        transaction.execute(
+            "INSERT INTO entries (address, hash, key, state, creation_time, last_used,
+                last_modified, reuse_count, refetch_count, request_time, response_time,
+                status_line, status_code, remote_host, remote_port)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+            params![
+                metadata.address.value,
+                metadata.hash,
+                metadata.key,
+                format!("{:?}", metadata.state),
+                to_rfc3339(metadata.creation_time),
+                metadata.last_used.and_then(to_rfc3339),
+                metadata.last_modified.and_then(to_rfc3339),
+                metadata.reuse_count,
+                metadata.refetch_count,
+                response_info
+                    .as_ref()
+                    .and_then(|info| to_rfc3339(info.request_time)),
+                response_info
+                    .as_ref()
+                    .and_then(|info| to_rfc3339(info.response_time)),
+                response_info.as_ref().map(|info| &info.headers.status_line),
+                response_info
+                    .as_ref()
+                    .and_then(|info| info.headers.status_code()),
+                metadata
+                    .remote_endpoint
+                    .as_ref()
+                    .map(|endpoint| &endpoint.host),
+                metadata
+                    .remote_endpoint
+                    .as_ref()
+                    .map(|endpoint| endpoint.port),
+            ],
+        )?;
+        let entry_id = transaction.last_insert_rowid();
+
+        if let Some(info) = &response_info {
+            for (position, (name, value)) in info.headers.headers.iter().enumerate() {
+                transaction.execute(
+                    "INSERT INTO response_headers (entry_id, position, name, value)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![entry_id, position, name, value],
+                )?;
+            }
+        }
+
+        for (stream, location) in locations.iter().enumerate() {
+            transaction.execute(
+                "INSERT INTO streams (entry_id, stream, size, address, file, offset)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    entry_id,
+                    stream,
+                    parsed.data_size[stream],
+                    parsed.data_addr[stream].value,
+                    location
+                        .as_ref()
+                        .map(|location| location.path.to_string_lossy()),
+                    location.as_ref().map(|location| location.offset),
+                ],
+            )?;
+
+            if options.include_bodies && stream > 0 && location.is_some() {
+                let mut data = Vec::new();
+                let read = entry
+                    .stream_reader(stream)
+                    .and_then(|mut reader| Ok(reader.read_to_end(&mut data)?));
+                if read.is_ok() {
+                    transaction.execute(
+                        "INSERT INTO bodies (entry_id, stream, data) VALUES (?1, ?2, ?3)",
+                        params![entry_id, stream, data],
+                    )?;
+                }
+            }
+        }
+
+        written += 1;
+    }
+
+    transaction.commit()?;
+    Ok(written)
+}
@@ -0,0 +1,209 @@
+//! Export of the times recorded for each entry as timeline formats that merge into forensic
+//! super-timelines: mactime bodyfiles and log2timeline CSV.
+//!
+//! See: https://wiki.sleuthkit.org/index.php?title=Body_file and
+//! https://plaso.readthedocs.io/en/latest/sources/user/Output-and-formatting.html
+use std::io::Write;
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    block_file::LazyBlockFileCacheEntry, export::EntryMetadata, response_info::HttpResponseInfo,
+    time::WindowsEpochMicroseconds, CCPResult, CacheKey, ChromeCache,
+};
+
+const L2T_CSV_HEADER: &str =
+    "date,time,timezone,MACB,source,sourcetype,type,user,host,short,desc,version,filename,inode,notes,format,extra";
+
+/// The times recorded for an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum TimelineEventKind {
+    /// The entry was created.
+    Created,
+    /// The request for the cached response was sent.
+    RequestSent,
+    /// The cached response was received.
+    ResponseReceived,
+    /// The entry was last modified.
+    LastModified,
+    /// The entry was last used.
+    LastUsed,
+}
+
+impl TimelineEventKind {
+    fn description(&self) -> &'static str {
+        match self {
+            TimelineEventKind::Created => "Creation Time",
+            TimelineEventKind::RequestSent => "Request Time",
+            TimelineEventKind::ResponseReceived => "Response Time",
+            TimelineEventKind::LastModified => "Last Modified Time",
+            TimelineEventKind::LastUsed => "Last Used Time",
+        }
+    }
+
+    /// The MACB (modified, accessed, changed, born) flags of the event.
+    fn macb(&self) -> &'static str {
+        match self {
+            TimelineEventKind::Created => "...B",
+            TimelineEventKind::RequestSent | TimelineEventKind::ResponseReceived => "..C.",
+            TimelineEventKind::LastModified => "M...",
+            TimelineEventKind::LastUsed => ".A..",
+        }
+    }
+}
+
+/// A single time recorded for an entry.
+#[derive(Debug, Clone)]
+pub struct TimelineEvent {
+    pub time: DateTime<Utc>,
+    pub kind: TimelineEventKind,
+    pub metadata: EntryMetadata,
+}
+
+/// The metadata of an entry along with its response info, when it can be parsed.
+fn entry_times(
+    entry: &mut LazyBlockFileCacheEntry,
+) -> CCPResult<(EntryMetadata, Option<HttpResponseInfo>)> {
+    let metadata = EntryMetadata::from_entry(entry)?;
+    Ok((metadata, entry.response_info().ok()))
+}
+
+/// Every valid time recorded for the entries of the cache, in chronological order. Entries that
+/// can't be parsed are skipped, as are unset times.
+pub fn timeline_events(cache: &ChromeCache) -> CCPResult<Vec<TimelineEvent>> {
+    let mut events = Vec::new();
+
+    for mut entry in cache.entries()? {
+        let Ok((metadata, info)) = entry_times(&mut entry) else {
+            continue;
+        };
+
+        let times = [
+            (TimelineEventKind::Created, Some(metadata.creation_time)),
+            (
+                TimelineEventKind::RequestSent,
+                info.as_ref().map(|info| info.request_time),
+            ),
+            (
+                TimelineEventKind::ResponseReceived,
+                info.as_ref().map(|info| info.response_time),
+            ),
+            (TimelineEventKind::LastModified, metadata.last_modified),
+            (TimelineEventKind::LastUsed, metadata.last_used),
+        ];
+
+        for (kind, time) in times {
+            if let Some(time) = time.and_then(|time| time.into_datetime_utc().ok()) {
+                events.push(TimelineEvent {
+                    time,
+                    kind,
+                    metadata: metadata.clone(),
+                });
+            }
+        }
+    }
+
+    events.sort_by_key(|event| (event.time, event.kind));
+    Ok(events)
+}
+
+/// Seconds since the unix epoch, or 0 (which mactime treats as unset) for invalid times.
+fn unix_seconds(time: Option<WindowsEpochMicroseconds>) -> i64 {
+    time.and_then(|time| time.into_datetime_utc().ok())
+        .map_or(0, |time| time.timestamp())
+}
+
+/// Write a mactime bodyfile with a line per entry. The entry's last used time is given as the
+/// access time, its last modified time as the modification time, the response time as the change
+/// time and its creation time as the birth time. The inode column holds the entry's address and
+/// the size column the size of its body. Returns the number of entries written.
+pub fn write_bodyfile<W: Write>(cache: &ChromeCache, mut writer: W) -> CCPResult<usize> {
+    let mut written = 0;
+
+    for mut entry in cache.entries()? {
+        let Ok((metadata, info)) = entry_times(&mut entry) else {
+            continue;
+        };
+
+        // `|` separates the columns, so it can't appear in the name.
+        let name = format!("chrome-cache:{}", metadata.key.replace('|', "%7C"));
+        writeln!(
+            writer,
+            "0|{}|{:08x}|r/----------|0|0|{}|{}|{}|{}|{}",
+            name,
+            metadata.address.value,
+            metadata.stream_sizes[1].max(0),
+            unix_seconds(metadata.last_used),
+            unix_seconds(metadata.last_modified),
+            unix_seconds(info.map(|info| info.response_time)),
+            unix_seconds(Some(metadata.creation_time)),
+        )?;
+        written += 1;
+    }
+
+    writer.flush()?;
+    Ok(written)
+}
+
+/// Quote a CSV field if needed.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Write a log2timeline (l2t_csv) CSV with a row per valid time recorded for each entry, in
+/// chronological order and in UTC. Returns the number of rows written.
+pub fn write_l2t_csv<W: Write>(cache: &ChromeCache, mut writer: W) -> CCPResult<usize> {
+    let events = timeline_events(cache)?;
+    let filename = cache.path().display().to_string();
+
+    writeln!(writer, "{}", L2T_CSV_HEADER)?;
+    for event in &events {
+        let metadata = &event.metadata;
+        let url = CacheKey::parse(&metadata.key).url;
+        let description = format!(
+            "Chrome cache entry {} {} (state {:?}, reuse count {}, refetch count {})",
+            event.kind.description(),
+            metadata.key,
+            metadata.state,
+            metadata.reuse_count,
+            metadata.refetch_count
+        );
+        let mut extra = format!(
+            "cache_address: 0x{:08x}; hash: 0x{:08x}; body_size: {}",
+            metadata.address.value, metadata.hash, metadata.stream_sizes[1]
+        );
+        if let Some(endpoint) = &metadata.remote_endpoint {
+            extra.push_str(&format!("; remote_endpoint: {}", endpoint));
+        }
+
+        let row = [
+            event.time.format("%m/%d/%Y").to_string(),
+            event.time.format("%H:%M:%S").to_string(),
+            "UTC".to_string(),
+            event.kind.macb().to_string(),
+            "WEBHIST".to_string(),
+            "Chrome Cache".to_string(),
+            event.kind.description().to_string(),
+            "-".to_string(),
+            "-".to_string(),
+            url,
+            description,
+            "2".to_string(),
+            filename.clone(),
+            "-".to_string(),
+            "-".to_string(),
+            env!("CARGO_PKG_NAME").to_string(),
+            extra,
+        ];
+        let row = row.iter().map(|field| csv_field(field)).collect::<Vec<_>>();
+        writeln!(writer, "{}", row.join(","))?;
+    }
+
+    writer.flush()?;
+    Ok(events.len())
+}
@@ -0,0 +1,204 @@
+//! Export of the cached responses as WARC 1.1 records, for web archive replay tools like pywb.
+//!
+//! See: https://iipc.github.io/warc-specifications/specifications/warc-format/warc-1.1/
+use std::io::Write;
+
+use chrono::{SecondsFormat, Utc};
+use flate2::{write::GzEncoder, Compression};
+use uuid::Uuid;
+
+use crate::{
+    block_file::LazyBlockFileCacheEntry, export::EntryMetadata, http_response::HttpResponseOptions,
+    time::WindowsEpochMicroseconds, CCPResult, CacheKey, ChromeCache,
+};
+
+const WARC_VERSION: &str = "WARC/1.1";
+
+/// Options for the WARC export.
+#[derive(Debug, Clone, Default)]
+pub struct WarcExportOptions {
+    /// Compress each record as a separate gzip member, as in `.warc.gz` files.
+    pub gzip: bool,
+}
+
+fn record_id() -> String {
+    format!("<urn:uuid:{}>", Uuid::new_v4())
+}
+
+fn to_warc_date(time: WindowsEpochMicroseconds) -> Option<String> {
+    time.into_datetime_utc()
+        .ok()
+        .map(|time| time.to_rfc3339_opts(SecondsFormat::Micros, true))
+}
+
+fn warc_fields(fields: &[(&str, Option<String>)]) -> Vec<u8> {
+    let mut block = Vec::new();
+    for (name, value) in fields {
+        if let Some(value) = value {
+            block.extend_from_slice(format!("{}: {}\r\n", name, value).as_bytes());
+        }
+    }
+    block
+}
+
+/// Writes WARC records, optionally compressing each one as a separate gzip member.
+struct RecordWriter<W: Write> {
+    writer: W,
+    gzip: bool,
+}
+
+impl<W: Write> RecordWriter<W> {
+    fn write(&mut self, headers: &[(&str, String)], block: &[u8]) -> CCPResult<()> {
+        let mut record = Vec::with_capacity(block.len() + 512);
+        record.extend_from_slice(WARC_VERSION.as_bytes());
+        record.extend_from_slice(b"\r\n");
+        for (name, value) in headers {
+            record.extend_from_slice(format!("{}: {}\r\n", name, value).as_bytes());
+        }
+        record.extend_from_slice(format!("Content-Length: {}\r\n\r\n", block.len()).as_bytes());
+        record.extend_from_slice(block);
+        record.extend_from_slice(b"\r\n\r\n");
+
+        if self.gzip {
+            let mut encoder = GzEncoder::new(&mut self.writer, Compression::default());
+            encoder.write_all(&record)?;
+            encoder.finish()?;
+        } else {
+            self.writer.write_all(&record)?;
+        }
+
+        Ok(())
+    }
+}
+
+type Record = (Vec<(&'static str, String)>, Vec<u8>);
+
+/// Build the `response` record of an entry, reconstructed from its response headers and stored
+/// body, along with a `metadata` record holding the cache's bookkeeping for the entry.
+fn entry_records(entry: &mut LazyBlockFileCacheEntry) -> CCPResult<[Record; 2]> {
+    let metadata = EntryMetadata::from_entry(entry)?;
+    let info = entry.response_info()?;
+    let url = CacheKey::parse(&metadata.key).url;
+    let date = to_warc_date(info.response_time)
+        .or_else(|| to_warc_date(metadata.creation_time))
+        .unwrap_or_else(|| Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true));
+
+    // The body is kept as stored, so it still matches the `Content-Encoding` of the headers.
+    let block = entry.to_http_response_bytes(&HttpResponseOptions::default())?;
+
+    let response_id = record_id();
+    let mut response_headers = vec![
+        ("WARC-Type", "response".to_string()),
+        ("WARC-Record-ID", response_id.clone()),
+        ("WARC-Date", date.clone()),
+        ("WARC-Target-URI", url.clone()),
+    ];
+    if let Some(ip) = metadata
+        .remote_endpoint
+        .as_ref()
+        .and_then(|endpoint| endpoint.ip())
+    {
+        response_headers.push(("WARC-IP-Address", ip.to_string()));
+    }
+    response_headers.push((
+        "Content-Type",
+        "application/http;msgtype=response".to_string(),
+    ));
+    let response = (response_headers, block);
+
+    let fields = warc_fields(&[
+        ("cache-key", Some(metadata.key.clone())),
+        (
+            "cache-address",
+            Some(format!("0x{:08x}", metadata.address.value)),
+        ),
+        ("cache-entry-state", Some(format!("{:?}", metadata.state))),
+        ("creation-time", to_warc_date(metadata.creation_time)),
+        ("last-used", metadata.last_used.and_then(to_warc_date)),
+        (
+            "last-modified",
+            metadata.last_modified.and_then(to_warc_date),
+        ),
+        ("reuse-count", Some(metadata.reuse_count.to_string())),
+        ("refetch-count", Some(metadata.refetch_count.to_string())),
+        ("request-time", to_warc_date(info.request_time)),
+        ("response-time", to_warc_date(info.response_time)),
+        (
+            "remote-endpoint",
+            metadata
+                .remote_endpoint
+                .as_ref()
+                .map(|endpoint| endpoint.to_string()),
+        ),
+    ]);
+    let metadata = (
+        vec![
+            ("WARC-Type", "metadata".to_string()),
+            ("WARC-Record-ID", record_id()),
+            ("WARC-Date", date),
+            ("WARC-Target-URI", url),
+            ("WARC-Concurrent-To", response_id),
+            ("Content-Type", "application/warc-fields".to_string()),
+        ],
+        fields,
+    );
+
+    Ok([response, metadata])
+}
+
+/// Write the cached responses as a WARC file: a `warcinfo` record, then a `response` and a
+/// `metadata` record per entry. Entries without parseable response info are skipped. Returns the
+/// number of entries written.
+pub fn write_warc<W: Write>(
+    cache: &ChromeCache,
+    writer: W,
+    options: &WarcExportOptions,
+) -> CCPResult<usize> {
+    let mut records = RecordWriter {
+        writer,
+        gzip: options.gzip,
+    };
+
+    let info = warc_fields(&[
+        (
+            "software",
+            Some(format!(
+                "{}/{}",
+                env!("CARGO_PKG_NAME"),
+                env!("CARGO_PKG_VERSION")
+            )),
+        ),
+        ("format", Some("WARC File Format 1.1".to_string())),
+        (
+            "description",
+            Some(format!("Chrome cache at {}", cache.path().display())),
+        ),
+    ]);
+    records.write(
+        &[
+            ("WARC-Type", "warcinfo".to_string()),
+            ("WARC-Record-ID", record_id()),
+            (
+                "WARC-Date",
+                Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+            ),
+            ("Content-Type", "application/warc-fields".to_string()),
+        ],
+        &info,
+    )?;
+
+    let mut written = 0;
+    for mut entry in cache.entries()? {
+        let Ok(entry_records) = entry_records(&mut entry) else {
+            continue;
+        };
+
+        for (headers, block) in &entry_records {
+            records.write(headers, block)?;
+        }
+        written += 1;
+    }
+
+    records.writer.flush()?;
+    Ok(written)
+}
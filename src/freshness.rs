@@ -0,0 +1,298 @@
+//! Freshness of cached responses, i.e. whether chrome would serve them without revalidating,
+//! following RFC 9111 along with the heuristics chrome applies when a response doesn't state its
+//! lifetime.
+//!
+//! See: https://www.rfc-editor.org/rfc/rfc9111#section-4.2 and
+//! https://chromium.googlesource.com/chromium/src/net/+/refs/heads/main/http/http_response_headers.cc
+use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
+
+use crate::response_info::HttpHeaders;
+
+/// Responses that chrome considers cacheable forever unless stated otherwise.
+const PERMANENT_STATUS_CODES: [u16; 4] = [300, 301, 308, 410];
+/// Responses whose freshness can be derived from `Last-Modified`.
+const HEURISTIC_STATUS_CODES: [u16; 3] = [200, 203, 206];
+/// The fraction of the time since `Last-Modified` that a response is heuristically fresh for.
+const LAST_MODIFIED_FRACTION: i32 = 10;
+
+/// Whether a cached response would be used without revalidation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum FreshnessStatus {
+    /// The response would be used as is.
+    Fresh,
+    /// The response would be used, but revalidated in the background (`stale-while-revalidate`).
+    StaleWhileRevalidate,
+    /// The response would be revalidated before being used.
+    Stale,
+}
+
+/// The freshness of a cached response at a given instant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Freshness {
+    /// How long the response is fresh for after it was generated. `TimeDelta::max_value()` for responses
+    /// that are fresh forever.
+    pub lifetime: TimeDelta,
+    /// How long past its lifetime the response may still be used while it's revalidated.
+    pub staleness: TimeDelta,
+    /// The age of the response at the instant.
+    pub current_age: TimeDelta,
+    pub status: FreshnessStatus,
+}
+
+/// Parse an HTTP date in any of the formats allowed by RFC 9110: IMF-fixdate, the obsolete RFC 850
+/// format and asctime.
+pub fn parse_http_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+
+    if let Ok(date) = DateTime::parse_from_rfc2822(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+
+    ["%A, %d-%b-%y %H:%M:%S GMT", "%a %b %e %H:%M:%S %Y"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|date| date.and_utc())
+}
+
+/// The values of a `Cache-Control` directive across every `Cache-Control` header, where
+/// directives without a value yield an empty string.
+fn cache_control<'a>(headers: &'a HttpHeaders, name: &'a str) -> impl Iterator<Item = &'a str> {
+    headers
+        .get_all("cache-control")
+        .flat_map(|value| value.split(','))
+        .filter_map(move |directive| {
+            let (directive, value) = directive.split_once('=').unwrap_or((directive, ""));
+            directive
+                .trim()
+                .eq_ignore_ascii_case(name)
+                .then(|| value.trim().trim_matches('"'))
+        })
+}
+
+fn has_directive(headers: &HttpHeaders, name: &str) -> bool {
+    cache_control(headers, name).next().is_some()
+}
+
+/// A number of seconds as a duration, saturating when it's out of range.
+fn seconds_saturating(seconds: i64) -> TimeDelta {
+    TimeDelta::try_seconds(seconds).unwrap_or(TimeDelta::max_value())
+}
+
+/// The value of a `Cache-Control` directive given in seconds, e.g. `max-age=600`.
+fn seconds_directive(headers: &HttpHeaders, name: &str) -> Option<TimeDelta> {
+    cache_control(headers, name)
+        .find_map(|value| value.parse::<i64>().ok())
+        .map(|seconds| seconds_saturating(seconds.max(0)))
+}
+
+/// The freshness lifetime of a response and how long past it the response may be used while it's
+/// revalidated, as chrome computes them.
+fn lifetimes(headers: &HttpHeaders, response_time: DateTime<Utc>) -> (TimeDelta, TimeDelta) {
+    let pragma_no_cache = headers
+        .get_all("pragma")
+        .flat_map(|value| value.split(','))
+        .any(|value| value.trim().eq_ignore_ascii_case("no-cache"));
+    if has_directive(headers, "no-cache") || has_directive(headers, "no-store") || pragma_no_cache {
+        return (TimeDelta::zero(), TimeDelta::zero());
+    }
+
+    let must_revalidate = has_directive(headers, "must-revalidate");
+    let staleness = if must_revalidate {
+        TimeDelta::zero()
+    } else {
+        seconds_directive(headers, "stale-while-revalidate").unwrap_or_default()
+    };
+
+    if let Some(max_age) = seconds_directive(headers, "max-age") {
+        return (max_age, staleness);
+    }
+
+    let date = headers
+        .get("date")
+        .and_then(parse_http_date)
+        .unwrap_or(response_time);
+
+    // An `Expires` header that can't be parsed (e.g. `0`) means the response has already expired.
+    if let Some(expires) = headers.get("expires") {
+        let lifetime = parse_http_date(expires)
+            .map(|expires| (expires - date).max(TimeDelta::zero()))
+            .unwrap_or_default();
+        return (lifetime, staleness);
+    }
+
+    let status = headers.status_code().unwrap_or_default();
+    if HEURISTIC_STATUS_CODES.contains(&status) && !must_revalidate {
+        if let Some(last_modified) = headers.get("last-modified").and_then(parse_http_date) {
+            if last_modified <= date {
+                return ((date - last_modified) / LAST_MODIFIED_FRACTION, staleness);
+            }
+        }
+    }
+
+    // Like chromium, permanent responses have no use for a stale-while-revalidate allowance.
+    if PERMANENT_STATUS_CODES.contains(&status) {
+        return (TimeDelta::max_value(), TimeDelta::zero());
+    }
+
+    (TimeDelta::zero(), staleness)
+}
+
+/// The age of a response at an instant, from the time it was generated (by its `Date` header)
+/// and how long it spent in caches along the way (by its `Age` header).
+fn current_age(
+    headers: &HttpHeaders,
+    request_time: DateTime<Utc>,
+    response_time: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> TimeDelta {
+    let date = headers
+        .get("date")
+        .and_then(parse_http_date)
+        .unwrap_or(response_time);
+    let age = headers
+        .get("age")
+        .and_then(|age| age.trim().parse::<i64>().ok())
+        .filter(|age| *age >= 0)
+        .map(seconds_saturating)
+        .unwrap_or_default();
+
+    let apparent_age = (response_time - date).max(TimeDelta::zero());
+    let response_delay = response_time - request_time;
+    let corrected_initial_age = apparent_age.max(
+        age.checked_add(&response_delay)
+            .unwrap_or(TimeDelta::max_value()),
+    );
+    let resident_time = now - response_time;
+
+    corrected_initial_age
+        .checked_add(&resident_time)
+        .unwrap_or(TimeDelta::max_value())
+}
+
+impl Freshness {
+    /// Compute the freshness at `now` of a response with the given headers, requested and
+    /// received at the given times.
+    pub fn compute(
+        headers: &HttpHeaders,
+        request_time: DateTime<Utc>,
+        response_time: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Freshness {
+        let (lifetime, staleness) = lifetimes(headers, response_time);
+        let current_age = current_age(headers, request_time, response_time, now);
+
+        // Responses varying on everything can never be reused.
+        let vary_all = headers
+            .get_all("vary")
+            .flat_map(|value| value.split(','))
+            .any(|value| value.trim() == "*");
+
+        let status = if vary_all {
+            FreshnessStatus::Stale
+        } else if lifetime > current_age {
+            FreshnessStatus::Fresh
+        } else if lifetime
+            .checked_add(&staleness)
+            .unwrap_or(TimeDelta::max_value())
+            > current_age
+        {
+            FreshnessStatus::StaleWhileRevalidate
+        } else {
+            FreshnessStatus::Stale
+        };
+
+        Freshness {
+            lifetime,
+            staleness,
+            current_age,
+            status,
+        }
+    }
+
+    /// Whether the response would be used without revalidation.
+    pub fn is_fresh(&self) -> bool {
+        self.status == FreshnessStatus::Fresh
+    }
+}
+
+#[test]
+fn test_freshness() {
+    let at = |seconds: i64| DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap();
+    let headers = |lines: &[&str]| HttpHeaders {
+        status_line: lines[0].to_string(),
+        headers: lines[1..]
+            .iter()
+            .map(|line| {
+                let (name, value) = line.split_once(": ").unwrap();
+                (name.to_string(), value.to_string())
+            })
+            .collect(),
+    };
+    let date = format!("Date: {}", at(0).format("%a, %d %b %Y %H:%M:%S GMT"));
+
+    let max_age = headers(&[
+        "HTTP/1.1 200 OK",
+        &date,
+        "Cache-Control: public, max-age=600",
+    ]);
+    let freshness = Freshness::compute(&max_age, at(-1), at(0), at(300));
+    assert_eq!(freshness.lifetime, TimeDelta::seconds(600));
+    assert_eq!(freshness.current_age, TimeDelta::seconds(301));
+    assert_eq!(freshness.status, FreshnessStatus::Fresh);
+    assert!(!Freshness::compute(&max_age, at(0), at(0), at(600)).is_fresh());
+
+    let swr = headers(&[
+        "HTTP/1.1 200 OK",
+        "Cache-Control: max-age=60, stale-while-revalidate=60",
+        "Age: 30",
+    ]);
+    let freshness = Freshness::compute(&swr, at(0), at(0), at(45));
+    assert_eq!(freshness.current_age, TimeDelta::seconds(75));
+    assert_eq!(freshness.status, FreshnessStatus::StaleWhileRevalidate);
+
+    let heuristic = headers(&[
+        "HTTP/1.1 200 OK",
+        &date,
+        "Last-Modified: Sat, 04 Nov 2023 22:13:20 GMT",
+    ]);
+    let freshness = Freshness::compute(&heuristic, at(0), at(0), at(0));
+    assert_eq!(freshness.lifetime, TimeDelta::days(10) / 10);
+
+    let expired = headers(&["HTTP/1.1 200 OK", &date, "Expires: 0"]);
+    assert!(!Freshness::compute(&expired, at(0), at(0), at(0)).is_fresh());
+
+    let redirect = headers(&[
+        "HTTP/1.1 301 Moved Permanently",
+        "Location: /",
+        "Cache-Control: stale-while-revalidate=60",
+    ]);
+    let freshness = Freshness::compute(&redirect, at(0), at(0), at(1_000_000_000));
+    assert!(freshness.is_fresh());
+    assert_eq!(freshness.staleness, TimeDelta::zero());
+
+    let no_cache = headers(&["HTTP/1.1 200 OK", "Cache-Control: max-age=600, no-cache"]);
+    assert!(!Freshness::compute(&no_cache, at(0), at(0), at(0)).is_fresh());
+
+    // Out of range durations saturate rather than overflowing.
+    let huge_max_age = headers(&[
+        "HTTP/1.1 200 OK",
+        "Cache-Control: max-age=99999999999999999",
+    ]);
+    let freshness = Freshness::compute(&huge_max_age, at(0), at(0), at(0));
+    assert_eq!(freshness.lifetime, TimeDelta::max_value());
+    assert!(freshness.is_fresh());
+
+    let huge_age = headers(&[
+        "HTTP/1.1 200 OK",
+        "Cache-Control: max-age=600",
+        "Age: 99999999999999999",
+    ]);
+    let freshness = Freshness::compute(&huge_age, at(-1), at(0), at(10));
+    assert_eq!(freshness.current_age, TimeDelta::max_value());
+    assert!(!freshness.is_fresh());
+
+    let negative_age = headers(&["HTTP/1.1 200 OK", "Cache-Control: max-age=600", "Age: -500"]);
+    let freshness = Freshness::compute(&negative_age, at(0), at(0), at(10));
+    assert_eq!(freshness.current_age, TimeDelta::seconds(10));
+}
@@ -0,0 +1,156 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+/// Paul Hsieh's SuperFastHash, which chrome uses to hash cache keys into the index table.
+///
+/// See: https://chromium.googlesource.com/chromium/src/+/refs/heads/main/base/third_party/superfasthash/superfasthash.c
+pub fn super_fast_hash(data: &[u8]) -> u32 {
+    if data.is_empty() {
+        return 0;
+    }
+
+    let get16bits = |bytes: &[u8]| u16::from_le_bytes([bytes[0], bytes[1]]) as u32;
+
+    let mut hash = data.len() as u32;
+    let mut chunks = data.chunks_exact(4);
+
+    for chunk in &mut chunks {
+        hash = hash.wrapping_add(get16bits(&chunk[0..2]));
+        let tmp = (get16bits(&chunk[2..4]) << 11) ^ hash;
+        hash = (hash << 16) ^ tmp;
+        hash = hash.wrapping_add(hash >> 11);
+    }
+
+    // The remaining bytes are sign extended, as chrome hashes them as `signed char`.
+    let rem = chunks.remainder();
+    match rem.len() {
+        3 => {
+            hash = hash.wrapping_add(get16bits(&rem[0..2]));
+            hash ^= hash << 16;
+            hash ^= ((rem[2] as i8 as i32) << 18) as u32;
+            hash = hash.wrapping_add(hash >> 11);
+        }
+        2 => {
+            hash = hash.wrapping_add(get16bits(&rem[0..2]));
+            hash ^= hash << 11;
+            hash = hash.wrapping_add(hash >> 17);
+        }
+        1 => {
+            hash = hash.wrapping_add(rem[0] as i8 as i32 as u32);
+            hash ^= hash << 10;
+            hash = hash.wrapping_add(hash >> 1);
+        }
+        _ => {}
+    }
+
+    hash ^= hash << 3;
+    hash = hash.wrapping_add(hash >> 5);
+    hash ^= hash << 4;
+    hash = hash.wrapping_add(hash >> 17);
+    hash ^= hash << 25;
+    hash = hash.wrapping_add(hash >> 6);
+
+    hash
+}
+
+/// The MD5, SHA-1 and SHA-256 digests of some data, as lowercase hex.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Digests {
+    /// The number of bytes digested.
+    pub size: u64,
+    pub md5: String,
+    pub sha1: String,
+    pub sha256: String,
+}
+
+impl Digests {
+    /// Digest everything the reader yields.
+    pub fn of_reader<R: Read>(reader: R) -> io::Result<Digests> {
+        let mut reader = DigestReader::new(reader);
+        io::copy(&mut reader, &mut io::sink())?;
+        Ok(reader.finish())
+    }
+
+    /// Digest the contents of a file.
+    pub fn of_file(path: &Path) -> io::Result<Digests> {
+        Digests::of_reader(File::open(path)?)
+    }
+}
+
+pub(crate) fn to_hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// A reader that digests everything read through it, so that data can be hashed in the same pass
+/// that consumes it.
+pub struct DigestReader<R> {
+    inner: R,
+    size: u64,
+    md5: Md5,
+    sha1: Sha1,
+    sha256: Sha256,
+}
+
+impl<R: Read> DigestReader<R> {
+    pub fn new(inner: R) -> DigestReader<R> {
+        DigestReader {
+            inner,
+            size: 0,
+            md5: Md5::new(),
+            sha1: Sha1::new(),
+            sha256: Sha256::new(),
+        }
+    }
+
+    /// The digests of the data read so far.
+    pub fn finish(self) -> Digests {
+        Digests {
+            size: self.size,
+            md5: to_hex(&self.md5.finalize()),
+            sha1: to_hex(&self.sha1.finalize()),
+            sha256: to_hex(&self.sha256.finalize()),
+        }
+    }
+}
+
+impl<R: Read> Read for DigestReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.size += read as u64;
+        self.md5.update(&buf[..read]);
+        self.sha1.update(&buf[..read]);
+        self.sha256.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+#[test]
+fn test_super_fast_hash() {
+    assert_eq!(super_fast_hash(b""), 0);
+    assert_eq!(super_fast_hash(b"a"), 0x115ea782);
+    assert_eq!(super_fast_hash(b"ab"), 0x516b8b44);
+    assert_eq!(super_fast_hash(b"abc"), 0xd2be198a);
+    assert_eq!(super_fast_hash(b"abcd"), 0xdad8b8db);
+    assert_eq!(super_fast_hash(b"\xff\xfe\xfd"), 0x547a507e);
+    assert_eq!(super_fast_hash(b"https://www.google.com/"), 0x7ea05665);
+}
+
+#[test]
+fn test_digests() {
+    let digests = Digests::of_reader(&b"abc"[..]).unwrap();
+    assert_eq!(digests.size, 3);
+    assert_eq!(digests.md5, "900150983cd24fb0d6963f7d28e17f72");
+    assert_eq!(digests.sha1, "a9993e364706816aba3e25717850c26c9cd0d89d");
+    assert_eq!(
+        digests.sha256,
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+}
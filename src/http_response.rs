@@ -0,0 +1,60 @@
+//! Reconstruction of raw HTTP/1.1 responses from cache entries: the status line and headers from
+//! the entry's first stream followed by the body from its second. See
+//! `LazyBlockFileCacheEntry::write_http_response`.
+use crate::response_info::HttpHeaders;
+
+/// How the reconstructed response is written.
+#[derive(Debug, Clone, Default)]
+pub struct HttpResponseOptions {
+    /// Undo the body's `Content-Encoding` and drop the header. Bodies with an unsupported
+    /// encoding are kept as stored.
+    pub decode: bool,
+    /// Replace any `Content-Length` with the length of the written body and drop
+    /// `Transfer-Encoding`, as chrome caches bodies without their transfer coding.
+    pub fix_content_length: bool,
+}
+
+/// The status line and headers of a response, terminated by an empty line. `Content-Encoding` is
+/// dropped when `decoded` is set, and `Content-Length` is set to `content_length` when given.
+pub fn response_head(headers: &HttpHeaders, decoded: bool, content_length: Option<u64>) -> Vec<u8> {
+    let mut head = Vec::new();
+    head.extend_from_slice(headers.status_line.as_bytes());
+    head.extend_from_slice(b"\r\n");
+
+    for (name, value) in &headers.headers {
+        let dropped = (decoded && name.eq_ignore_ascii_case("content-encoding"))
+            || (content_length.is_some()
+                && (name.eq_ignore_ascii_case("content-length")
+                    || name.eq_ignore_ascii_case("transfer-encoding")));
+        if !dropped {
+            head.extend_from_slice(format!("{}: {}\r\n", name, value).as_bytes());
+        }
+    }
+    if let Some(content_length) = content_length {
+        head.extend_from_slice(format!("Content-Length: {}\r\n", content_length).as_bytes());
+    }
+
+    head.extend_from_slice(b"\r\n");
+    head
+}
+
+#[test]
+fn test_response_head() {
+    let headers = HttpHeaders::from_raw(
+        b"HTTP/1.1 200 OK\0Content-Type: text/html\0Content-Encoding: gzip\0\
+          Transfer-Encoding: chunked\0Content-Length: 10\0\0",
+    );
+
+    let head = response_head(&headers, false, None);
+    assert_eq!(
+        String::from_utf8(head).unwrap(),
+        "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Encoding: gzip\r\n\
+         Transfer-Encoding: chunked\r\nContent-Length: 10\r\n\r\n"
+    );
+
+    let head = response_head(&headers, true, Some(42));
+    assert_eq!(
+        String::from_utf8(head).unwrap(),
+        "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 42\r\n\r\n"
+    );
+}
@@ -3,15 +3,39 @@
 pub mod block_file;
 pub mod cache_address;
 pub mod cache_index;
+pub mod cache_index_v3;
+pub mod cache_key;
+pub mod content_encoding;
+pub mod cookies;
+pub mod describe;
+pub mod discover;
 pub mod error;
+pub mod export;
+pub mod freshness;
+pub mod hash;
+pub mod http_response;
+pub mod pickle;
+pub mod response_info;
+pub mod rules;
+pub mod search;
+pub mod sniff;
+pub mod source_location;
+pub mod ssl_info;
 pub mod time;
+pub mod vary;
 
-pub use crate::cache_address::CacheAddr;
-use crate::cache_index::CacheVersion;
-pub use crate::cache_index::IndexHeader;
+pub use crate::cache_address::{CacheAddr, FileType};
+pub use crate::cache_index::{CacheVersion, IndexHeader};
+pub use crate::cache_index_v3::IndexTableV3;
+pub use crate::cache_key::CacheKey;
+pub use crate::describe::{Describe, StructDescription};
 pub use crate::error::{CCPError, CCPResult};
+pub use crate::source_location::SourceLocation;
 
-use block_file::{DataFiles, LazyBlockFileCacheEntry, LazyBlockFileCacheEntryIterator};
+use block_file::{
+    DataFiles, LazyBlockFile, LazyBlockFileCacheEntry, LazyBlockFileCacheEntryIterator,
+    LazyEntryRecord, BLOCK_FILE_TYPES, BLOCK_FILE_TYPES_V3,
+};
 use cache_address::CACHE_ADDRESS_SIZE;
 use cache_index::INDEX_HEADER_SIZE;
 use std::cell::RefCell;
@@ -42,12 +66,16 @@ impl ChromeCache {
         let mut f = fs::File::open(index)?;
         f.read_to_end(&mut index_buffer)?;
 
+        if index_buffer.len() < INDEX_HEADER_SIZE {
+            return Err(CCPError::InvalidData("index file is truncated".to_string()));
+        }
+
         let chrome_cache = ChromeCache {
             path,
             buffer: index_buffer,
         };
 
-        let header = ChromeCache::header(&chrome_cache)?;
+        let header = ChromeCache::raw_header(&chrome_cache)?;
         let version = CacheVersion::from(header.version);
 
         if header.magic != cache_index::INDEX_MAGIC {
@@ -64,12 +92,79 @@ impl ChromeCache {
         Ok(chrome_cache)
     }
 
-    pub fn header(&self) -> CCPResult<&IndexHeader> {
+    /// The path to the cache directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The index header, transmuted with the version 2 layout. Only the magic and version are
+    /// shared with the version 3.0 layout.
+    fn raw_header(&self) -> CCPResult<&IndexHeader> {
         IndexHeader::ref_from(&self.buffer[0..INDEX_HEADER_SIZE]).ok_or(CCPError::DataMisalignment(
             "index header misalignment".to_string(),
         ))
     }
 
+    pub fn version(&self) -> CCPResult<CacheVersion> {
+        Ok(CacheVersion::from(self.raw_header()?.version))
+    }
+
+    /// The header of a version 2.0 or 2.1 index. Version 3.0 indexes are laid out differently, see
+    /// `ChromeCache::index_v3`.
+    pub fn header(&self) -> CCPResult<&IndexHeader> {
+        if self.version()? == CacheVersion::Version3_0 {
+            return Err(CCPError::InvalidState(
+                "version 3.0 index headers must be read through the v3 index".to_string(),
+            ));
+        }
+
+        self.raw_header()
+    }
+
+    /// Load the version 3.0 index: the bitmap from the `index` file along with the main and extra
+    /// hash tables from `index_tb1` and `index_tb2`.
+    pub fn index_v3(&self) -> CCPResult<IndexTableV3> {
+        if self.version()? != CacheVersion::Version3_0 {
+            return Err(CCPError::InvalidState(
+                "only version 3.0 caches have a v3 index".to_string(),
+            ));
+        }
+
+        let main_table = fs::read(self.path.join("index_tb1"))?;
+        let extra_table = match fs::read(self.path.join("index_tb2")) {
+            Ok(table) => table,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(IndexTableV3::new(
+            self.buffer.clone(),
+            main_table,
+            extra_table,
+        ))
+    }
+
+    /// Iterate over the entry records of a version 3.0 cache, one for every used cell of the
+    /// index table.
+    pub fn entry_records(&self) -> CCPResult<impl Iterator<Item = CCPResult<LazyEntryRecord>>> {
+        let cells = self.index_v3()?.cells()?;
+        let cache_path = self.path.to_path_buf();
+        let data_files = self.data_files();
+
+        Ok(cells.into_iter().map(move |cell| {
+            let addr = cell.cell.address();
+            let location = SourceLocation::of(addr, &cache_path)?;
+            let buffer = data_files.borrow_mut().get_entry(&addr)?;
+            Ok(LazyEntryRecord::new(
+                cell.cell,
+                buffer,
+                location,
+                Rc::clone(&data_files),
+                cache_path.clone(),
+            ))
+        }))
+    }
+
     pub fn addresses(&self) -> CCPResult<&[CacheAddr]> {
         let table_len = self.header()?.table_len as usize;
         let begin = INDEX_HEADER_SIZE;
@@ -81,16 +176,74 @@ impl ChromeCache {
         Ok(addresses.into_slice())
     }
 
+    /// Load every block file in the cache, following the chain of files for each block type. Their
+    /// headers expose the fill level and allocation bitmap of each file.
+    pub fn block_files(&self) -> CCPResult<Vec<LazyBlockFile>> {
+        let mut data_files = DataFiles::new(HashMap::new(), self.path.to_path_buf());
+
+        let file_types: &[FileType] = match self.version()? {
+            CacheVersion::Version3_0 => &BLOCK_FILE_TYPES_V3,
+            _ => &BLOCK_FILE_TYPES,
+        };
+
+        let mut files = Vec::new();
+        for &file_type in file_types {
+            files.extend(data_files.chain(file_type)?);
+        }
+
+        Ok(files)
+    }
+
+    /// Load every block file holding blocks of the given type, starting with its first file and
+    /// following each header's `next_file` link.
+    pub fn block_file_chain(&self, file_type: FileType) -> CCPResult<Vec<LazyBlockFile>> {
+        DataFiles::new(HashMap::new(), self.path.to_path_buf()).chain(file_type)
+    }
+
     fn path_to_index(cache_dir: &Path) -> PathBuf {
         cache_dir.join("index")
     }
 
-    pub fn entries(&self) -> CCPResult<impl Iterator<Item = LazyBlockFileCacheEntry> + '_> {
-        // A map from the data file number to the data file.
-        let data_files = Rc::new(RefCell::new(DataFiles::new(
+    /// Find the entry with the given key by hashing it into the index table, the same way chrome
+    /// looks entries up. Entries of the bucket whose record or key can't be read are skipped.
+    pub fn lookup(&self, key: &str) -> CCPResult<Option<LazyBlockFileCacheEntry>> {
+        let hash = hash::super_fast_hash(key.as_bytes());
+        let addresses = self.addresses()?;
+
+        if addresses.is_empty() {
+            return Ok(None);
+        }
+
+        let addr = addresses[hash as usize & (addresses.len() - 1)];
+        if !addr.is_initialized() {
+            return Ok(None);
+        }
+
+        for entry in
+            LazyBlockFileCacheEntryIterator::new(self.data_files(), addr, self.path.clone())
+        {
+            if entry.get().is_ok_and(|record| record.hash == hash)
+                && entry.key().is_ok_and(|entry_key| entry_key == key)
+            {
+                return Ok(Some(entry));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn data_files(&self) -> Rc<RefCell<DataFiles>> {
+        Rc::new(RefCell::new(DataFiles::new(
             HashMap::new(),
             self.path.to_path_buf(),
-        )));
+        )))
+    }
+
+    /// Iterate over the entries of a version 2.0 or 2.1 cache. Version 3.0 caches store entry
+    /// records instead, see `ChromeCache::entry_records`.
+    pub fn entries(&self) -> CCPResult<impl Iterator<Item = LazyBlockFileCacheEntry> + '_> {
+        // A map from the data file number to the data file.
+        let data_files = self.data_files();
 
         let entries = self
             .addresses()?
@@ -0,0 +1,241 @@
+//! Reading and writing of `base::Pickle`, the serialization chrome uses for the response info of
+//! cache entries among others: a header holding the payload size followed by the payload, where
+//! every value is aligned to 4 bytes.
+//!
+//! See: https://chromium.googlesource.com/chromium/src/+/refs/heads/main/base/pickle.h
+use crate::{CCPError, CCPResult};
+
+/// The alignment of every value in the payload.
+const PICKLE_ALIGNMENT: usize = 4;
+/// The size of the default header, which only holds the payload size.
+const PICKLE_HEADER_SIZE: usize = 4;
+
+/// Reads the values of a pickle in the order they were written.
+pub struct PickleReader<'a> {
+    payload: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> PickleReader<'a> {
+    /// Read a pickle with the default header.
+    pub fn new(data: &'a [u8]) -> CCPResult<PickleReader<'a>> {
+        PickleReader::with_header_size(data, PICKLE_HEADER_SIZE)
+    }
+
+    /// Read a pickle with a custom header, which starts with the payload size and is followed by
+    /// fields of its own.
+    pub fn with_header_size(data: &'a [u8], header_size: usize) -> CCPResult<PickleReader<'a>> {
+        if header_size < PICKLE_HEADER_SIZE || header_size % PICKLE_ALIGNMENT != 0 {
+            return Err(CCPError::InvalidData(format!(
+                "invalid pickle header size {}",
+                header_size
+            )));
+        }
+
+        let header = data.get(0..header_size).ok_or(CCPError::InvalidData(
+            "pickle header is truncated".to_string(),
+        ))?;
+        let payload_size = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let payload = data
+            .get(header_size..header_size + payload_size as usize)
+            .ok_or(CCPError::InvalidData(format!(
+                "pickle payload of {} bytes is truncated",
+                payload_size
+            )))?;
+
+        Ok(PickleReader { payload, offset: 0 })
+    }
+
+    /// Whether every value of the payload has been read.
+    pub fn is_at_end(&self) -> bool {
+        self.offset >= self.payload.len()
+    }
+
+    /// Read `len` raw bytes, skipping the padding that follows them.
+    pub fn read_bytes(&mut self, len: usize) -> CCPResult<&'a [u8]> {
+        let bytes = self
+            .offset
+            .checked_add(len)
+            .and_then(|end| self.payload.get(self.offset..end))
+            .ok_or(CCPError::InvalidData(format!(
+                "pickle read of {} bytes at {} is out of bounds",
+                len, self.offset
+            )))?;
+        self.offset += len.next_multiple_of(PICKLE_ALIGNMENT);
+        Ok(bytes)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> CCPResult<[u8; N]> {
+        let mut value = [0; N];
+        value.copy_from_slice(self.read_bytes(N)?);
+        Ok(value)
+    }
+
+    pub fn read_bool(&mut self) -> CCPResult<bool> {
+        Ok(self.read_i32()? != 0)
+    }
+
+    pub fn read_u16(&mut self) -> CCPResult<u16> {
+        Ok(u16::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_i32(&mut self) -> CCPResult<i32> {
+        Ok(i32::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u32(&mut self) -> CCPResult<u32> {
+        Ok(u32::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_i64(&mut self) -> CCPResult<i64> {
+        Ok(i64::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u64(&mut self) -> CCPResult<u64> {
+        Ok(u64::from_le_bytes(self.read_array()?))
+    }
+
+    /// Read a non-negative count, e.g. of the elements of a list.
+    pub fn read_length(&mut self) -> CCPResult<usize> {
+        let len = self.read_i32()?;
+        if len < 0 {
+            return Err(CCPError::InvalidData(format!(
+                "negative pickle length {}",
+                len
+            )));
+        }
+        Ok(len as usize)
+    }
+
+    /// Read a string as its raw bytes, which aren't necessarily UTF-8.
+    pub fn read_string(&mut self) -> CCPResult<&'a [u8]> {
+        let len = self.read_length()?;
+        self.read_bytes(len)
+    }
+
+    /// Read a UTF-16 string, replacing invalid code units.
+    pub fn read_string16(&mut self) -> CCPResult<String> {
+        let len = self.read_length()?;
+        let bytes = self.read_bytes(len.checked_mul(2).ok_or(CCPError::InvalidData(
+            format!("pickle string16 length {} overflows", len),
+        ))?)?;
+        let units = bytes
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .collect::<Vec<_>>();
+        Ok(String::from_utf16_lossy(&units))
+    }
+
+    /// Read a length-prefixed blob of data.
+    pub fn read_data(&mut self) -> CCPResult<&'a [u8]> {
+        self.read_string()
+    }
+}
+
+/// Writes values into a pickle with the default header, mirroring `PickleReader`.
+#[derive(Debug, Clone, Default)]
+pub struct PickleWriter {
+    payload: Vec<u8>,
+}
+
+impl PickleWriter {
+    pub fn new() -> PickleWriter {
+        PickleWriter::default()
+    }
+
+    /// Write raw bytes, padded with zeros to the alignment.
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        self.payload.extend_from_slice(bytes);
+        let padded = self.payload.len().next_multiple_of(PICKLE_ALIGNMENT);
+        self.payload.resize(padded, 0);
+        self
+    }
+
+    pub fn write_bool(&mut self, value: bool) -> &mut Self {
+        self.write_i32(value as i32)
+    }
+
+    pub fn write_u16(&mut self, value: u16) -> &mut Self {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    pub fn write_i32(&mut self, value: i32) -> &mut Self {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    pub fn write_u32(&mut self, value: u32) -> &mut Self {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    pub fn write_i64(&mut self, value: i64) -> &mut Self {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    pub fn write_u64(&mut self, value: u64) -> &mut Self {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    pub fn write_string(&mut self, value: &[u8]) -> &mut Self {
+        self.write_i32(value.len() as i32);
+        self.write_bytes(value)
+    }
+
+    pub fn write_string16(&mut self, value: &str) -> &mut Self {
+        let units = value.encode_utf16().collect::<Vec<_>>();
+        self.write_i32(units.len() as i32);
+        let bytes = units
+            .iter()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect::<Vec<_>>();
+        self.write_bytes(&bytes)
+    }
+
+    pub fn write_data(&mut self, value: &[u8]) -> &mut Self {
+        self.write_string(value)
+    }
+
+    /// The pickle: the header followed by the payload.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(PICKLE_HEADER_SIZE + self.payload.len());
+        data.extend_from_slice(&(self.payload.len() as u32).to_le_bytes());
+        data.extend_from_slice(&self.payload);
+        data
+    }
+}
+
+#[test]
+fn test_pickle() {
+    let data = PickleWriter::new()
+        .write_bool(true)
+        .write_u16(443)
+        .write_i32(-7)
+        .write_u32(u32::MAX)
+        .write_i64(-1 << 40)
+        .write_u64(1 << 63)
+        .write_string(b"abcde")
+        .write_string16("h\u{e9}llo \u{1f600}")
+        .write_data(&[0, 1, 2])
+        .to_bytes();
+    // The header, 4 values padded to 4 bytes, 2 8-byte values, then each length followed by its
+    // padded data.
+    assert_eq!(data.len(), 4 + 4 * 4 + 2 * 8 + (4 + 8) + (4 + 16) + (4 + 4));
+
+    let mut pickle = PickleReader::new(&data).unwrap();
+    assert!(pickle.read_bool().unwrap());
+    assert_eq!(pickle.read_u16().unwrap(), 443);
+    assert_eq!(pickle.read_i32().unwrap(), -7);
+    assert_eq!(pickle.read_u32().unwrap(), u32::MAX);
+    assert_eq!(pickle.read_i64().unwrap(), -1 << 40);
+    assert_eq!(pickle.read_u64().unwrap(), 1 << 63);
+    assert_eq!(pickle.read_string().unwrap(), b"abcde");
+    assert_eq!(pickle.read_string16().unwrap(), "h\u{e9}llo \u{1f600}");
+    assert_eq!(pickle.read_data().unwrap(), [0, 1, 2]);
+    assert!(pickle.is_at_end());
+    assert!(pickle.read_u32().is_err());
+
+    let truncated = PickleReader::new(&data[..data.len() - 1]);
+    assert!(truncated.is_err());
+
+    let negative = PickleWriter::new().write_i32(-1).to_bytes();
+    assert!(PickleReader::new(&negative).unwrap().read_string().is_err());
+}
@@ -0,0 +1,407 @@
+//! Parsing of the `HttpResponseInfo` chrome persists in the first stream of each entry, holding
+//! the response headers along with metadata about the request.
+//!
+//! See: https://chromium.googlesource.com/chromium/src/net/+/refs/heads/main/http/http_response_info.cc
+use std::{fmt, net::IpAddr};
+
+use crate::{
+    pickle::PickleReader,
+    ssl_info::{SignedCertificateTimestamp, SslInfo},
+    time::WindowsEpochMicroseconds,
+    vary::HttpVaryData,
+    CCPError, CCPResult,
+};
+
+const RESPONSE_INFO_MINIMUM_VERSION: u32 = 1;
+const RESPONSE_INFO_VERSION: u32 = 3;
+const RESPONSE_INFO_VERSION_MASK: u32 = 0xFF;
+const RESPONSE_INFO_HAS_CERT: u32 = 1 << 8;
+const RESPONSE_INFO_HAS_SECURITY_BITS: u32 = 1 << 9;
+const RESPONSE_INFO_HAS_CERT_STATUS: u32 = 1 << 10;
+const RESPONSE_INFO_HAS_VARY_DATA: u32 = 1 << 11;
+const RESPONSE_INFO_HAS_SSL_CONNECTION_STATUS: u32 = 1 << 16;
+const RESPONSE_INFO_HAS_ALPN_NEGOTIATED_PROTOCOL: u32 = 1 << 17;
+const RESPONSE_INFO_HAS_CONNECTION_INFO: u32 = 1 << 18;
+const RESPONSE_INFO_HAS_SIGNED_CERTIFICATE_TIMESTAMPS: u32 = 1 << 20;
+const RESPONSE_INFO_HAS_KEY_EXCHANGE_GROUP: u32 = 1 << 22;
+const RESPONSE_INFO_HAS_STALENESS: u32 = 1 << 24;
+const RESPONSE_INFO_HAS_PEER_SIGNATURE_ALGORITHM: u32 = 1 << 25;
+const RESPONSE_INFO_HAS_EXTRA_FLAGS: u32 = 1 << 31;
+const RESPONSE_EXTRA_INFO_HAS_ORIGINAL_RESPONSE_TIME: u32 = 1 << 2;
+
+/// The status line and headers of a cached response.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HttpHeaders {
+    pub status_line: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl HttpHeaders {
+    /// Parse headers in chrome's raw format, where the status line and each header are terminated
+    /// by a NUL byte rather than a line break.
+    pub fn from_raw(raw: &[u8]) -> HttpHeaders {
+        let raw = String::from_utf8_lossy(raw);
+        let mut lines = raw.split('\0').filter(|line| !line.is_empty());
+
+        let status_line = lines.next().unwrap_or_default().to_string();
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+            .collect();
+
+        HttpHeaders {
+            status_line,
+            headers,
+        }
+    }
+
+    /// The status code from the status line, e.g. 200 for `HTTP/1.1 200 OK`.
+    pub fn status_code(&self) -> Option<u16> {
+        self.status_line.split_whitespace().nth(1)?.parse().ok()
+    }
+
+    /// The value of the first header with the given (case insensitive) name.
+    pub fn get<'a>(&'a self, name: &'a str) -> Option<&'a str> {
+        self.get_all(name).next()
+    }
+
+    /// The values of every header with the given (case insensitive) name, in order.
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.headers
+            .iter()
+            .filter(move |(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// The remote endpoint a response was received from. For responses fetched through a proxy, this
+/// is the proxy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RemoteEndpoint {
+    /// The host of the endpoint, normally an IP address.
+    pub host: String,
+    pub port: u16,
+}
+
+impl RemoteEndpoint {
+    /// The IP address of the endpoint, when the host is one.
+    pub fn ip(&self) -> Option<IpAddr> {
+        self.host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse()
+            .ok()
+    }
+}
+
+impl fmt::Display for RemoteEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.ip() {
+            Some(IpAddr::V6(ip)) => write!(f, "[{}]:{}", ip, self.port),
+            _ => write!(f, "{}:{}", self.host, self.port),
+        }
+    }
+}
+
+/// The response metadata chrome persists alongside each cached response.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HttpResponseInfo {
+    /// The version of the persisted format, in the low byte, along with flags describing which
+    /// optional fields are present.
+    pub flags: u32,
+    /// When the request that produced the response was issued.
+    pub request_time: WindowsEpochMicroseconds,
+    /// When the response was received.
+    pub response_time: WindowsEpochMicroseconds,
+    pub headers: HttpHeaders,
+    /// The TLS details, for responses fetched over a secure connection.
+    pub ssl_info: Option<SslInfo>,
+    /// The digest of the request headers named by the `Vary` header, for responses that vary on
+    /// any.
+    pub vary_data: Option<HttpVaryData>,
+    /// The endpoint the response was received from, unless chrome didn't record one (e.g. for
+    /// responses synthesized by a service worker).
+    pub remote_endpoint: Option<RemoteEndpoint>,
+    /// The protocol negotiated with ALPN, e.g. `h2`.
+    pub alpn_negotiated_protocol: Option<String>,
+    /// The `HttpConnectionInfo` the response was received over, see
+    /// `HttpResponseInfo::connection_protocol`.
+    pub connection_info: Option<i32>,
+}
+
+impl HttpResponseInfo {
+    /// Parse the response info from the pickled contents of an entry's first stream.
+    pub fn from_pickle(data: &[u8]) -> CCPResult<HttpResponseInfo> {
+        let mut pickle = PickleReader::new(data)?;
+
+        let flags = pickle.read_u32()?;
+        let version = flags & RESPONSE_INFO_VERSION_MASK;
+        if !(RESPONSE_INFO_MINIMUM_VERSION..=RESPONSE_INFO_VERSION).contains(&version) {
+            return Err(CCPError::UnsupportedVersion(format!(
+                "response info version {}",
+                version
+            )));
+        }
+
+        let extra_flags = match flags & RESPONSE_INFO_HAS_EXTRA_FLAGS {
+            0 => 0,
+            _ => pickle.read_u32()?,
+        };
+
+        let request_time = WindowsEpochMicroseconds::new(pickle.read_i64()? as u64);
+        let response_time = WindowsEpochMicroseconds::new(pickle.read_i64()? as u64);
+
+        if extra_flags & RESPONSE_EXTRA_INFO_HAS_ORIGINAL_RESPONSE_TIME != 0 {
+            pickle.read_i64()?;
+        }
+
+        let headers = HttpHeaders::from_raw(pickle.read_string()?);
+
+        let mut ssl_info = SslInfo::default();
+        if flags & RESPONSE_INFO_HAS_CERT != 0 {
+            let count = pickle.read_length()?;
+            for _ in 0..count {
+                ssl_info.certificates.push(pickle.read_string()?.to_vec());
+            }
+        }
+        if flags & RESPONSE_INFO_HAS_CERT_STATUS != 0 {
+            ssl_info.cert_status = Some(pickle.read_u32()?);
+        }
+        if flags & RESPONSE_INFO_HAS_SECURITY_BITS != 0 {
+            pickle.read_i32()?;
+        }
+        if flags & RESPONSE_INFO_HAS_SSL_CONNECTION_STATUS != 0 {
+            ssl_info.connection_status = Some(pickle.read_i32()?);
+        }
+        if flags & RESPONSE_INFO_HAS_SIGNED_CERTIFICATE_TIMESTAMPS != 0 {
+            let count = pickle.read_length()?;
+            for _ in 0..count {
+                // Each timestamp is followed by the status of its verification.
+                let sct = read_signed_certificate_timestamp(&mut pickle)?;
+                let status = pickle.read_u16()?;
+                ssl_info
+                    .signed_certificate_timestamps
+                    .push(SignedCertificateTimestamp { status, ..sct });
+            }
+        }
+
+        let vary_data = match flags & RESPONSE_INFO_HAS_VARY_DATA {
+            0 => None,
+            _ => {
+                let mut request_digest = [0; 16];
+                request_digest.copy_from_slice(pickle.read_bytes(16)?);
+                Some(HttpVaryData { request_digest })
+            }
+        };
+
+        let host = String::from_utf8_lossy(pickle.read_string()?).to_string();
+        let port = pickle.read_u16()?;
+        let remote_endpoint = (!host.is_empty()).then_some(RemoteEndpoint { host, port });
+
+        let alpn_negotiated_protocol = match flags & RESPONSE_INFO_HAS_ALPN_NEGOTIATED_PROTOCOL {
+            0 => None,
+            _ => Some(String::from_utf8_lossy(pickle.read_string()?).to_string()),
+        };
+        let connection_info = match flags & RESPONSE_INFO_HAS_CONNECTION_INFO {
+            0 => None,
+            _ => Some(pickle.read_i32()?),
+        };
+
+        if flags & RESPONSE_INFO_HAS_KEY_EXCHANGE_GROUP != 0 {
+            ssl_info.key_exchange_group = Some(pickle.read_i32()?);
+        }
+        if flags & RESPONSE_INFO_HAS_STALENESS != 0 {
+            pickle.read_i64()?;
+        }
+        if flags & RESPONSE_INFO_HAS_PEER_SIGNATURE_ALGORITHM != 0 {
+            ssl_info.peer_signature_algorithm = Some(pickle.read_i32()?);
+        }
+
+        let ssl_info = (ssl_info != SslInfo::default()).then_some(ssl_info);
+
+        Ok(HttpResponseInfo {
+            flags,
+            request_time,
+            response_time,
+            headers,
+            ssl_info,
+            vary_data,
+            remote_endpoint,
+            alpn_negotiated_protocol,
+            connection_info,
+        })
+    }
+
+    /// Whether chrome would serve the cached response for a request with the given headers, as far
+    /// as its `Vary` header is concerned. Responses without vary data match every request.
+    pub fn matches_request(&self, request_headers: &[(&str, &str)]) -> bool {
+        self.vary_data
+            .is_none_or(|vary_data| vary_data.matches_request(&self.headers, request_headers))
+    }
+
+    /// The protocol the response was received over, e.g. `HTTP/2` or `QUIC`, from its connection
+    /// info.
+    ///
+    /// See: https://chromium.googlesource.com/chromium/src/net/+/refs/heads/main/http/http_connection_info.h
+    pub fn connection_protocol(&self) -> Option<&'static str> {
+        Some(match self.connection_info? {
+            1 => "HTTP/1.1",
+            2 | 3 => "SPDY",
+            4 | 6 | 7 => "HTTP/2",
+            8 => "HTTP/0.9",
+            9 => "HTTP/1.0",
+            5 | 10.. => "QUIC",
+            _ => return None,
+        })
+    }
+}
+
+/// Read a signed certificate timestamp as written by `SignedCertificateTimestamp::Persist`. The
+/// status of its verification follows it in the pickle and is left for the caller to read.
+///
+/// See: https://chromium.googlesource.com/chromium/src/net/+/refs/heads/main/cert/signed_certificate_timestamp.cc
+fn read_signed_certificate_timestamp(
+    pickle: &mut PickleReader,
+) -> CCPResult<SignedCertificateTimestamp> {
+    let version = pickle.read_i32()?;
+    let log_id = pickle.read_string()?.to_vec();
+    let timestamp = WindowsEpochMicroseconds::new(pickle.read_i64()? as u64);
+    // The extensions and the signature's hash algorithm, signature algorithm and data.
+    pickle.read_string()?;
+    pickle.read_i32()?;
+    pickle.read_i32()?;
+    pickle.read_string()?;
+    let origin = pickle.read_i32()?;
+    let log_description = String::from_utf8_lossy(pickle.read_string()?).to_string();
+
+    Ok(SignedCertificateTimestamp {
+        status: 0,
+        version,
+        origin,
+        log_id,
+        timestamp,
+        log_description,
+    })
+}
+
+#[test]
+fn test_remote_endpoint() {
+    let v4 = RemoteEndpoint {
+        host: "93.184.215.14".to_string(),
+        port: 443,
+    };
+    assert_eq!(v4.ip(), Some(IpAddr::from([93, 184, 215, 14])));
+    assert_eq!(v4.to_string(), "93.184.215.14:443");
+
+    let v6 = RemoteEndpoint {
+        host: "[2606:2800:220:1::1946]".to_string(),
+        port: 8443,
+    };
+    assert!(v6.ip().is_some_and(|ip| ip.is_ipv6()));
+    assert_eq!(v6.to_string(), "[2606:2800:220:1::1946]:8443");
+}
+
+#[test]
+fn test_response_info_from_pickle() {
+    use crate::pickle::PickleWriter;
+
+    let flags = RESPONSE_INFO_VERSION
+        | RESPONSE_INFO_HAS_CERT
+        | RESPONSE_INFO_HAS_CERT_STATUS
+        | RESPONSE_INFO_HAS_SSL_CONNECTION_STATUS
+        | RESPONSE_INFO_HAS_SIGNED_CERTIFICATE_TIMESTAMPS
+        | RESPONSE_INFO_HAS_VARY_DATA
+        | RESPONSE_INFO_HAS_ALPN_NEGOTIATED_PROTOCOL
+        | RESPONSE_INFO_HAS_CONNECTION_INFO
+        | RESPONSE_INFO_HAS_KEY_EXCHANGE_GROUP
+        | RESPONSE_INFO_HAS_STALENESS
+        | RESPONSE_INFO_HAS_PEER_SIGNATURE_ALGORITHM
+        | RESPONSE_INFO_HAS_EXTRA_FLAGS;
+
+    let mut pickle = PickleWriter::new();
+    pickle
+        .write_u32(flags)
+        .write_u32(RESPONSE_EXTRA_INFO_HAS_ORIGINAL_RESPONSE_TIME)
+        .write_i64(13_300_000_000_000_000)
+        .write_i64(13_300_000_000_500_000)
+        .write_i64(13_299_999_999_000_000)
+        .write_string(b"HTTP/1.1 200 OK\0Content-Type: text/css\0Vary: Accept\0\0")
+        // The certificate chain, cert status and connection status.
+        .write_i32(2)
+        .write_string(b"leaf")
+        .write_string(b"root")
+        .write_u32(1 << 17)
+        .write_i32((5 << 20) | 0xc02f)
+        // Two signed certificate timestamps, each in the order of
+        // `SignedCertificateTimestamp::Persist` followed by its status.
+        .write_i32(2)
+        .write_i32(0)
+        .write_string(&[7; 32])
+        .write_i64(13_290_000_000_000_000)
+        .write_string(b"")
+        .write_i32(4)
+        .write_i32(3)
+        .write_string(b"signature")
+        .write_i32(0)
+        .write_string(b"Example Log")
+        .write_u16(1)
+        .write_i32(0)
+        .write_string(&[8; 32])
+        .write_i64(13_291_000_000_000_000)
+        .write_string(b"ext")
+        .write_i32(4)
+        .write_i32(3)
+        .write_string(b"other signature")
+        .write_i32(2)
+        .write_string(b"Other Log")
+        .write_u16(3)
+        // The vary data, remote endpoint, ALPN and connection info.
+        .write_bytes(&[9; 16])
+        .write_string(b"192.0.2.1")
+        .write_u16(8443)
+        .write_string(b"h2")
+        .write_i32(4)
+        // The key exchange group, staleness and signature algorithm.
+        .write_i32(23)
+        .write_i64(0)
+        .write_i32(0x0403);
+
+    let info = HttpResponseInfo::from_pickle(&pickle.to_bytes()).unwrap();
+    assert_eq!(info.request_time.as_micros(), 13_300_000_000_000_000);
+    assert_eq!(info.response_time.as_micros(), 13_300_000_000_500_000);
+    assert_eq!(info.headers.get("content-type"), Some("text/css"));
+
+    let ssl_info = info.ssl_info.unwrap();
+    assert_eq!(ssl_info.certificates, [b"leaf".to_vec(), b"root".to_vec()]);
+    assert_eq!(ssl_info.cert_status, Some(1 << 17));
+    assert_eq!(ssl_info.tls_version(), Some("TLS 1.2"));
+    assert_eq!(ssl_info.cipher_suite(), Some(0xc02f));
+    assert_eq!(ssl_info.key_exchange_group_name(), Some("P-256"));
+    assert_eq!(
+        ssl_info.peer_signature_algorithm_name(),
+        Some("ecdsa_secp256r1_sha256")
+    );
+    let scts = &ssl_info.signed_certificate_timestamps;
+    assert_eq!(scts.len(), 2);
+    assert_eq!(scts[0].log_id, [7; 32]);
+    assert_eq!(scts[0].log_description, "Example Log");
+    assert_eq!((scts[0].status, scts[0].origin), (1, 0));
+    assert_eq!(scts[1].log_id, [8; 32]);
+    assert_eq!(scts[1].timestamp.as_micros(), 13_291_000_000_000_000);
+    assert_eq!(scts[1].log_description, "Other Log");
+    assert_eq!((scts[1].status, scts[1].origin), (3, 2));
+
+    assert_eq!(info.vary_data.unwrap().request_digest, [9; 16]);
+    assert_eq!(info.remote_endpoint.unwrap().to_string(), "192.0.2.1:8443");
+    assert_eq!(info.alpn_negotiated_protocol.as_deref(), Some("h2"));
+    assert_eq!(info.connection_info, Some(4));
+
+    let unsupported = PickleWriter::new().write_u32(0x7f).to_bytes();
+    assert!(matches!(
+        HttpResponseInfo::from_pickle(&unsupported),
+        Err(CCPError::UnsupportedVersion(_))
+    ));
+}
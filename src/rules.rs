@@ -0,0 +1,814 @@
+//! Scanning of decoded bodies with YARA-style rules, for triaging cached malware.
+//!
+//! A subset of the YARA rule language is supported: text strings (with the `nocase`, `ascii`,
+//! `wide` and `fullword` modifiers), hex strings (with `??` and nibble wildcards, jumps and
+//! alternatives), regular expressions, and conditions combining `$a`, `#a > n`, `filesize`,
+//! `any`/`all`/`none`/`n of them`, `and`, `or`, `not` and parentheses. Modules and `at`/`in`
+//! conditions are not.
+//!
+//! See: https://yara.readthedocs.io/en/stable/writingrules.html
+//!
+//! ```text
+//! rule suspicious_script : js {
+//!     meta:
+//!         author = "analyst"
+//!     strings:
+//!         $eval = "eval(" nocase
+//!         $mz = { 4D 5A ?? 00 }
+//!         $b64 = /atob\(['"][A-Za-z0-9+\/=]{200,}/
+//!     condition:
+//!         $eval and ($b64 or #mz > 2)
+//! }
+//! ```
+use regex::bytes::{Regex, RegexBuilder};
+
+use crate::{search, CCPError, CCPResult, CacheAddr, CacheVersion, ChromeCache};
+
+#[derive(Debug, Clone, Copy)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    fn compare(self, a: u64, b: u64) -> bool {
+        match self {
+            Comparison::Eq => a == b,
+            Comparison::Ne => a != b,
+            Comparison::Lt => a < b,
+            Comparison::Le => a <= b,
+            Comparison::Gt => a > b,
+            Comparison::Ge => a >= b,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Quantity {
+    Any,
+    All,
+    None,
+    AtLeast(usize),
+}
+
+/// A rule's condition, with strings referred to by their index in the rule.
+#[derive(Debug, Clone)]
+enum Condition {
+    Bool(bool),
+    Matched(usize),
+    Count(usize, Comparison, u64),
+    FileSize(Comparison, u64),
+    Of(Quantity, Vec<usize>),
+    Not(Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+impl Condition {
+    fn eval(&self, offsets: &[Vec<usize>], file_size: u64) -> bool {
+        match self {
+            Condition::Bool(value) => *value,
+            Condition::Matched(string) => !offsets[*string].is_empty(),
+            Condition::Count(string, comparison, count) => {
+                comparison.compare(offsets[*string].len() as u64, *count)
+            }
+            Condition::FileSize(comparison, size) => comparison.compare(file_size, *size),
+            Condition::Of(quantity, strings) => {
+                let matched = strings
+                    .iter()
+                    .filter(|string| !offsets[**string].is_empty())
+                    .count();
+                match quantity {
+                    Quantity::Any => matched > 0,
+                    Quantity::All => matched == strings.len(),
+                    Quantity::None => matched == 0,
+                    Quantity::AtLeast(count) => matched >= *count,
+                }
+            }
+            Condition::Not(condition) => !condition.eval(offsets, file_size),
+            Condition::And(a, b) => a.eval(offsets, file_size) && b.eval(offsets, file_size),
+            Condition::Or(a, b) => a.eval(offsets, file_size) || b.eval(offsets, file_size),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RuleString {
+    identifier: String,
+    pattern: Regex,
+    fullword: bool,
+}
+
+impl RuleString {
+    /// The offsets of the string's non-overlapping matches in `data`.
+    fn find(&self, data: &[u8]) -> Vec<usize> {
+        self.pattern
+            .find_iter(data)
+            .filter(|found| {
+                !self.fullword
+                    || !(found.start() > 0 && data[found.start() - 1].is_ascii_alphanumeric()
+                        || data.get(found.end()).is_some_and(u8::is_ascii_alphanumeric))
+            })
+            .map(|found| found.start())
+            .collect()
+    }
+}
+
+/// A parsed rule.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub name: String,
+    pub tags: Vec<String>,
+    /// The `meta` section, with every value as a string.
+    pub meta: Vec<(String, String)>,
+    strings: Vec<RuleString>,
+    condition: Condition,
+}
+
+/// The matches of one of a rule's strings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StringMatch {
+    /// The string's identifier, including the `$`.
+    pub identifier: String,
+    pub offsets: Vec<usize>,
+}
+
+impl Rule {
+    /// The matches of the rule's strings in `data`, when its condition holds.
+    pub fn scan(&self, data: &[u8]) -> Option<Vec<StringMatch>> {
+        let offsets = self
+            .strings
+            .iter()
+            .map(|string| string.find(data))
+            .collect::<Vec<_>>();
+        if !self.condition.eval(&offsets, data.len() as u64) {
+            return None;
+        }
+
+        Some(
+            self.strings
+                .iter()
+                .zip(offsets)
+                .filter(|(_, offsets)| !offsets.is_empty())
+                .map(|(string, offsets)| StringMatch {
+                    identifier: string.identifier.clone(),
+                    offsets,
+                })
+                .collect(),
+        )
+    }
+}
+
+/// A match of a rule against the body of an entry.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RuleMatch {
+    pub address: CacheAddr,
+    pub key: String,
+    pub rule: String,
+    pub tags: Vec<String>,
+    pub strings: Vec<StringMatch>,
+}
+
+/// Scan the body of every entry, decoded from its `Content-Encoding` when possible, with every
+/// rule in one pass over the cache, calling `on_match` with each rule that matches. Entries that
+/// can't be read are skipped. Returns the number of matches.
+///
+/// Version 3.0 caches aren't supported, as their entry records don't keep the key.
+pub fn scan<F>(cache: &ChromeCache, rules: &[Rule], mut on_match: F) -> CCPResult<usize>
+where
+    F: FnMut(RuleMatch) -> CCPResult<()>,
+{
+    if cache.version()? == CacheVersion::Version3_0 {
+        return Err(CCPError::UnsupportedVersion(
+            "version 3.0 caches can't be scanned".to_string(),
+        ));
+    }
+
+    let mut found = 0;
+
+    for entry in cache.entries()? {
+        let (Ok(key), Ok(body)) = (entry.key(), search::body(&entry)) else {
+            continue;
+        };
+
+        for rule in rules {
+            if let Some(strings) = rule.scan(&body) {
+                found += 1;
+                on_match(RuleMatch {
+                    address: entry.location().addr,
+                    key: key.clone(),
+                    rule: rule.name.clone(),
+                    tags: rule.tags.clone(),
+                    strings,
+                })?;
+            }
+        }
+    }
+
+    Ok(found)
+}
+
+/// Parse the rules of a YARA-style rules file.
+pub fn parse_rules(source: &str) -> CCPResult<Vec<Rule>> {
+    let mut parser = Parser {
+        source: source.as_bytes(),
+        pos: 0,
+    };
+    let mut rules: Vec<Rule> = Vec::new();
+
+    while parser.peek().is_some() {
+        let rule = parser.rule()?;
+        if rules.iter().any(|other| other.name == rule.name) {
+            return Err(parser.error(&format!("duplicate rule {}", rule.name)));
+        }
+        rules.push(rule);
+    }
+
+    Ok(rules)
+}
+
+fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+fn escape_bytes(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("\\x{:02x}", byte))
+        .collect()
+}
+
+struct Parser<'a> {
+    source: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &str) -> CCPError {
+        let line = self.source[..self.pos]
+            .iter()
+            .filter(|byte| **byte == b'\n')
+            .count()
+            + 1;
+        CCPError::InvalidRule(format!("line {}: {}", line, message))
+    }
+
+    fn skip_space(&mut self) {
+        loop {
+            let rest = &self.source[self.pos..];
+            if rest.first().is_some_and(u8::is_ascii_whitespace) {
+                self.pos += 1;
+            } else if rest.starts_with(b"//") {
+                self.pos += rest
+                    .iter()
+                    .position(|byte| *byte == b'\n')
+                    .unwrap_or(rest.len());
+            } else if rest.starts_with(b"/*") {
+                self.pos += rest
+                    .windows(2)
+                    .position(|end| end == b"*/")
+                    .map_or(rest.len(), |end| end + 2);
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// The next byte after any whitespace and comments.
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_space();
+        self.source.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let eaten = self.peek() == Some(byte);
+        if eaten {
+            self.pos += 1;
+        }
+        eaten
+    }
+
+    fn expect(&mut self, byte: u8) -> CCPResult<()> {
+        if self.eat(byte) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", byte as char)))
+        }
+    }
+
+    fn peek_word(&mut self) -> &'a str {
+        self.skip_space();
+        let len = self.source[self.pos..]
+            .iter()
+            .take_while(|byte| is_word_byte(**byte))
+            .count();
+        // Only ASCII bytes were taken.
+        std::str::from_utf8(&self.source[self.pos..self.pos + len]).unwrap_or_default()
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        let eaten = self.peek_word() == word;
+        if eaten {
+            self.pos += word.len();
+        }
+        eaten
+    }
+
+    fn identifier(&mut self) -> CCPResult<String> {
+        let word = self.peek_word();
+        if word.is_empty() || word.as_bytes()[0].is_ascii_digit() {
+            return Err(self.error("expected an identifier"));
+        }
+        self.pos += word.len();
+        Ok(word.to_string())
+    }
+
+    fn number(&mut self) -> CCPResult<u64> {
+        let word = self.peek_word();
+        let (digits, multiplier) = match (word.strip_suffix("KB"), word.strip_suffix("MB")) {
+            (Some(digits), _) => (digits, 1 << 10),
+            (_, Some(digits)) => (digits, 1 << 20),
+            _ => (word, 1),
+        };
+        let value = match digits.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => digits.parse(),
+        }
+        .map_err(|_| self.error("expected a number"))?
+        .checked_mul(multiplier)
+        .ok_or_else(|| self.error("number out of range"))?;
+        self.pos += word.len();
+        Ok(value)
+    }
+
+    fn rule(&mut self) -> CCPResult<Rule> {
+        if !self.eat_word("rule") {
+            return Err(self.error("expected 'rule'"));
+        }
+        let name = self.identifier()?;
+        let mut tags = Vec::new();
+        if self.eat(b':') {
+            while self.peek() != Some(b'{') {
+                tags.push(self.identifier()?);
+            }
+        }
+        self.expect(b'{')?;
+
+        let mut meta = Vec::new();
+        if self.eat_word("meta") {
+            self.expect(b':')?;
+            while !matches!(self.peek_word(), "strings" | "condition") {
+                let name = self.identifier()?;
+                self.expect(b'=')?;
+                let value = match self.peek() {
+                    Some(b'"') => {
+                        self.pos += 1;
+                        String::from_utf8_lossy(&self.text()?).into_owned()
+                    }
+                    _ => {
+                        let word = self.peek_word();
+                        self.pos += word.len();
+                        word.to_string()
+                    }
+                };
+                meta.push((name, value));
+            }
+        }
+
+        let mut strings = Vec::new();
+        if self.eat_word("strings") {
+            self.expect(b':')?;
+            while self.eat(b'$') {
+                let identifier = format!("${}", self.identifier()?);
+                if strings
+                    .iter()
+                    .any(|string: &RuleString| string.identifier == identifier)
+                {
+                    return Err(self.error(&format!("duplicate string {}", identifier)));
+                }
+                self.expect(b'=')?;
+                strings.push(self.string(identifier)?);
+            }
+        }
+
+        if !self.eat_word("condition") {
+            return Err(self.error("expected 'condition'"));
+        }
+        self.expect(b':')?;
+        let condition = self.or(&strings)?;
+        self.expect(b'}')?;
+
+        Ok(Rule {
+            name,
+            tags,
+            meta,
+            strings,
+            condition,
+        })
+    }
+
+    /// The value of a string definition and its modifiers.
+    fn string(&mut self, identifier: String) -> CCPResult<RuleString> {
+        let mut fullword = false;
+        let pattern = match self.peek() {
+            Some(b'"') => {
+                self.pos += 1;
+                let text = self.text()?;
+                if text.is_empty() {
+                    return Err(self.error(&format!("empty string {}", identifier)));
+                }
+                let (mut nocase, mut ascii, mut wide) = (false, false, false);
+                loop {
+                    match self.peek_word() {
+                        "nocase" => nocase = true,
+                        "ascii" => ascii = true,
+                        "wide" => wide = true,
+                        "fullword" => fullword = true,
+                        _ => break,
+                    }
+                    self.pos += self.peek_word().len();
+                }
+
+                let mut alternatives = Vec::new();
+                if ascii || !wide {
+                    alternatives.push(escape_bytes(&text));
+                }
+                if wide {
+                    let wide = text.iter().flat_map(|byte| [*byte, 0]).collect::<Vec<_>>();
+                    alternatives.push(escape_bytes(&wide));
+                }
+                let flags = if nocase { "i-u" } else { "-u" };
+                format!("(?{}:{})", flags, alternatives.join("|"))
+            }
+            Some(b'{') => {
+                self.pos += 1;
+                self.hex()?
+            }
+            Some(b'/') => {
+                self.pos += 1;
+                self.regex()?
+            }
+            _ => return Err(self.error("expected a string, hex string or regular expression")),
+        };
+
+        let pattern = RegexBuilder::new(&pattern)
+            .unicode(false)
+            .build()
+            .map_err(|err| self.error(&format!("invalid string {} ({})", identifier, err)))?;
+        Ok(RuleString {
+            identifier,
+            pattern,
+            fullword,
+        })
+    }
+
+    /// The bytes of a text string, after its opening quote.
+    fn text(&mut self) -> CCPResult<Vec<u8>> {
+        let mut text = Vec::new();
+        loop {
+            let byte = *self
+                .source
+                .get(self.pos)
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += 1;
+            match byte {
+                b'"' => return Ok(text),
+                b'\n' => return Err(self.error("unterminated string")),
+                b'\\' => {
+                    let escaped = *self
+                        .source
+                        .get(self.pos)
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.pos += 1;
+                    text.push(match escaped {
+                        b'n' => b'\n',
+                        b'r' => b'\r',
+                        b't' => b'\t',
+                        b'"' | b'\\' => escaped,
+                        b'x' => {
+                            let hex = self
+                                .source
+                                .get(self.pos..self.pos + 2)
+                                .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                                .and_then(|hex| std::str::from_utf8(hex).ok())
+                                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                                .ok_or_else(|| self.error("invalid \\x escape"))?;
+                            self.pos += 2;
+                            hex
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    });
+                }
+                _ => text.push(byte),
+            }
+        }
+    }
+
+    /// A hex string, after its opening brace, as a regular expression.
+    fn hex(&mut self) -> CCPResult<String> {
+        let mut pattern = String::from("(?s-u:");
+        loop {
+            match self.peek() {
+                Some(b'}') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(b'(') => {
+                    self.pos += 1;
+                    pattern.push_str("(?:");
+                }
+                Some(b'|') => {
+                    self.pos += 1;
+                    pattern.push('|');
+                }
+                Some(b')') => {
+                    self.pos += 1;
+                    pattern.push(')');
+                }
+                Some(b'[') => {
+                    self.pos += 1;
+                    let min = match self.peek() {
+                        Some(b'-') => 0,
+                        _ => self.number()?,
+                    };
+                    let max = if !self.eat(b'-') {
+                        min.to_string()
+                    } else if self.peek() == Some(b']') {
+                        String::new()
+                    } else {
+                        self.number()?.to_string()
+                    };
+                    self.expect(b']')?;
+                    pattern.push_str(&format!(".{{{},{}}}", min, max));
+                }
+                Some(high) if high == b'?' || high.is_ascii_hexdigit() => {
+                    let low = self
+                        .source
+                        .get(self.pos + 1)
+                        .copied()
+                        .filter(|low| *low == b'?' || low.is_ascii_hexdigit())
+                        .ok_or_else(|| self.error("incomplete hex byte"))?;
+                    self.pos += 2;
+                    let nibble = |digit: u8| (digit as char).to_digit(16).unwrap_or(0) as u8;
+                    pattern.push_str(&match (high, low) {
+                        (b'?', b'?') => ".".to_string(),
+                        (b'?', low) => format!(
+                            "[{}]",
+                            (0..16)
+                                .map(|high| format!("\\x{:02x}", high << 4 | nibble(low)))
+                                .collect::<String>()
+                        ),
+                        (high, b'?') => format!(
+                            "[\\x{:02x}-\\x{:02x}]",
+                            nibble(high) << 4,
+                            nibble(high) << 4 | 0xf
+                        ),
+                        (high, low) => format!("\\x{:02x}", nibble(high) << 4 | nibble(low)),
+                    });
+                }
+                _ => return Err(self.error("invalid hex string")),
+            }
+        }
+        pattern.push(')');
+        Ok(pattern)
+    }
+
+    /// A regular expression, after its opening slash, with its flags applied.
+    fn regex(&mut self) -> CCPResult<String> {
+        let mut pattern = String::new();
+        loop {
+            let byte = *self
+                .source
+                .get(self.pos)
+                .ok_or_else(|| self.error("unterminated regular expression"))?;
+            self.pos += 1;
+            match byte {
+                b'/' => break,
+                b'\n' => return Err(self.error("unterminated regular expression")),
+                b'\\' if self.source.get(self.pos) == Some(&b'/') => {
+                    self.pos += 1;
+                    pattern.push('/');
+                }
+                b'\\' => match self.source.get(self.pos) {
+                    Some(escaped) if escaped.is_ascii() => {
+                        self.pos += 1;
+                        pattern.push('\\');
+                        pattern.push(*escaped as char);
+                    }
+                    _ => {}
+                },
+                // Rules match bytes, so a non-ASCII character matches the bytes of its UTF-8
+                // encoding, as in YARA.
+                _ if !byte.is_ascii() => pattern.push_str(&escape_bytes(&[byte])),
+                _ => pattern.push(byte as char),
+            }
+        }
+
+        let mut flags = String::new();
+        while let Some(flag) = self
+            .source
+            .get(self.pos)
+            .filter(|flag| matches!(flag, b'i' | b's'))
+        {
+            flags.push(*flag as char);
+            self.pos += 1;
+        }
+        Ok(format!("(?{}-u:{})", flags, pattern))
+    }
+
+    fn or(&mut self, strings: &[RuleString]) -> CCPResult<Condition> {
+        let mut condition = self.and(strings)?;
+        while self.eat_word("or") {
+            condition = Condition::Or(Box::new(condition), Box::new(self.and(strings)?));
+        }
+        Ok(condition)
+    }
+
+    fn and(&mut self, strings: &[RuleString]) -> CCPResult<Condition> {
+        let mut condition = self.not(strings)?;
+        while self.eat_word("and") {
+            condition = Condition::And(Box::new(condition), Box::new(self.not(strings)?));
+        }
+        Ok(condition)
+    }
+
+    fn not(&mut self, strings: &[RuleString]) -> CCPResult<Condition> {
+        if self.eat_word("not") {
+            Ok(Condition::Not(Box::new(self.not(strings)?)))
+        } else {
+            self.primary(strings)
+        }
+    }
+
+    fn comparison(&mut self) -> CCPResult<Comparison> {
+        self.skip_space();
+        let rest = &self.source[self.pos..];
+        let (comparison, len) = match rest {
+            [b'=', b'=', ..] => (Comparison::Eq, 2),
+            [b'!', b'=', ..] => (Comparison::Ne, 2),
+            [b'<', b'=', ..] => (Comparison::Le, 2),
+            [b'>', b'=', ..] => (Comparison::Ge, 2),
+            [b'<', ..] => (Comparison::Lt, 1),
+            [b'>', ..] => (Comparison::Gt, 1),
+            _ => return Err(self.error("expected a comparison")),
+        };
+        self.pos += len;
+        Ok(comparison)
+    }
+
+    /// The index of the string with the identifier following a `$` or `#`.
+    fn string_index(&mut self, strings: &[RuleString]) -> CCPResult<usize> {
+        let identifier = format!("${}", self.identifier()?);
+        strings
+            .iter()
+            .position(|string| string.identifier == identifier)
+            .ok_or_else(|| self.error(&format!("undefined string {}", identifier)))
+    }
+
+    fn primary(&mut self, strings: &[RuleString]) -> CCPResult<Condition> {
+        if self.eat(b'(') {
+            let condition = self.or(strings)?;
+            self.expect(b')')?;
+            return Ok(condition);
+        }
+        if self.eat(b'$') {
+            return Ok(Condition::Matched(self.string_index(strings)?));
+        }
+        if self.eat(b'#') {
+            let string = self.string_index(strings)?;
+            let comparison = self.comparison()?;
+            return Ok(Condition::Count(string, comparison, self.number()?));
+        }
+
+        let quantity = match self.peek_word() {
+            "true" | "false" => {
+                let value = self.peek_word() == "true";
+                self.pos += self.peek_word().len();
+                return Ok(Condition::Bool(value));
+            }
+            "filesize" => {
+                self.pos += "filesize".len();
+                let comparison = self.comparison()?;
+                return Ok(Condition::FileSize(comparison, self.number()?));
+            }
+            "any" => Quantity::Any,
+            "all" => Quantity::All,
+            "none" => Quantity::None,
+            word if word
+                .bytes()
+                .next()
+                .is_some_and(|byte| byte.is_ascii_digit()) =>
+            {
+                Quantity::AtLeast(self.number()? as usize)
+            }
+            _ => return Err(self.error("expected a condition")),
+        };
+        if !matches!(quantity, Quantity::AtLeast(_)) {
+            self.pos += self.peek_word().len();
+        }
+        if !self.eat_word("of") {
+            return Err(self.error("expected 'of'"));
+        }
+        Ok(Condition::Of(quantity, self.string_set(strings)?))
+    }
+
+    /// `them` or a parenthesized list of strings, which may end in `*` to match by prefix.
+    fn string_set(&mut self, strings: &[RuleString]) -> CCPResult<Vec<usize>> {
+        if self.eat_word("them") {
+            if strings.is_empty() {
+                return Err(self.error("the rule has no strings"));
+            }
+            return Ok((0..strings.len()).collect());
+        }
+
+        self.expect(b'(')?;
+        let mut set = Vec::new();
+        loop {
+            self.expect(b'$')?;
+            let prefix = format!("${}", self.peek_word());
+            self.pos += prefix.len() - 1;
+            let matching = if self.source.get(self.pos) == Some(&b'*') {
+                self.pos += 1;
+                (0..strings.len())
+                    .filter(|string| strings[*string].identifier.starts_with(&prefix))
+                    .collect::<Vec<_>>()
+            } else {
+                (0..strings.len())
+                    .filter(|string| strings[*string].identifier == prefix)
+                    .collect()
+            };
+            if matching.is_empty() {
+                return Err(self.error(&format!("undefined string {}", prefix)));
+            }
+            for string in matching {
+                if !set.contains(&string) {
+                    set.push(string);
+                }
+            }
+
+            if !self.eat(b',') {
+                break;
+            }
+        }
+        self.expect(b')')?;
+        Ok(set)
+    }
+}
+
+#[test]
+fn test_rules() {
+    let rules = parse_rules(
+        r#"
+        // Comments are skipped.
+        rule script : js web {
+            meta:
+                author = "analyst"
+                score = 80
+            strings:
+                $eval = "EVAL(" nocase
+                $wide = "hi" wide
+                $word = "key" fullword
+                $hex = { 4D 5A ?? [1-2] ( 01 | 02 ) 3? }
+                $re = /ab+c\/d/i
+            condition:
+                ($eval or #hex >= 2) and not $word and 2 of ($w*, $re) and filesize < 1KB
+        }
+        rule none_of_them { strings: $a = "x" condition: none of them }
+        "#,
+    )
+    .unwrap();
+    assert_eq!(rules.len(), 2);
+    assert_eq!(rules[0].tags, ["js", "web"]);
+    assert_eq!(rules[0].meta[1], ("score".to_string(), "80".to_string()));
+
+    let matches = rules[0]
+        .scan(b"eval(h\0i\0 ABBC/D keys MZ\0\0\x01\x35")
+        .unwrap();
+    let identifiers = matches
+        .iter()
+        .map(|found| found.identifier.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(identifiers, ["$eval", "$wide", "$hex", "$re"]);
+    assert_eq!(matches[2].offsets, [22]);
+
+    assert!(rules[0].scan(b"eval( key h\0i\0 abc/d").is_none());
+    assert!(rules[0].scan(&[b'x'; 2048]).is_none());
+    assert!(rules[1].scan(b"y").is_some() && rules[1].scan(b"x").is_none());
+
+    assert!(parse_rules("rule a { condition: $a }").is_err());
+    assert!(parse_rules("rule a { strings: $a = { 4 } condition: $a }").is_err());
+    assert!(parse_rules("rule a { condition: true } rule a { condition: false }").is_err());
+    assert!(parse_rules(r#"rule a { strings: $a = "" condition: $a }"#).is_err());
+    assert!(parse_rules(r#"rule a { strings: $a = "\x+f" condition: $a }"#).is_err());
+    assert!(parse_rules("rule a { condition: filesize < 0xffffffffffffffffMB }").is_err());
+
+    let rules = parse_rules("rule a { strings: $a = /caf\u{e9}+/ condition: $a }").unwrap();
+    assert!(rules[0].scan("un café".as_bytes()).is_some());
+    assert!(rules[0].scan(b"caf\xe9").is_none());
+}
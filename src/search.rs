@@ -0,0 +1,149 @@
+//! Searching the keys, response headers and decoded bodies of every entry for a pattern, without
+//! extracting them first.
+use std::io::Read;
+
+use regex::bytes::Regex;
+
+use crate::{
+    block_file::LazyBlockFileCacheEntry, CCPError, CCPResult, CacheAddr, CacheVersion, ChromeCache,
+};
+
+/// The part of an entry a match was found in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum SearchTarget {
+    Key,
+    /// The stored response info, holding the response headers.
+    Headers,
+    /// The response body, after undoing its `Content-Encoding`.
+    Body,
+}
+
+/// Which parts of the entries to search.
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    pub keys: bool,
+    pub headers: bool,
+    pub bodies: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            keys: true,
+            headers: true,
+            bodies: true,
+        }
+    }
+}
+
+/// A match of the pattern in an entry.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SearchMatch {
+    pub address: CacheAddr,
+    pub key: String,
+    pub target: SearchTarget,
+    /// The stream the match is in: none for keys, 0 for headers and 1 for bodies.
+    pub stream: Option<usize>,
+    /// The offset of the match in the key, the stored stream or the decoded body.
+    pub offset: usize,
+    pub matched: Vec<u8>,
+}
+
+/// A pattern matching the given bytes literally.
+pub fn literal_pattern(bytes: &[u8]) -> CCPResult<Regex> {
+    let escaped = bytes
+        .iter()
+        .map(|byte| format!("\\x{:02x}", byte))
+        .collect::<String>();
+    Ok(Regex::new(&format!("(?-u:{})", escaped))?)
+}
+
+/// The decoded body of an entry, or the stored body when it can't be decoded.
+pub(crate) fn body(entry: &LazyBlockFileCacheEntry) -> CCPResult<Vec<u8>> {
+    let mut body = Vec::new();
+    let decoded = entry
+        .decoded_body_reader()
+        .and_then(|mut reader| Ok(reader.read_to_end(&mut body)?));
+
+    if decoded.is_err() {
+        body.clear();
+        entry.stream_reader(1)?.read_to_end(&mut body)?;
+    }
+    Ok(body)
+}
+
+/// Search the entries of the cache for a pattern, calling `on_match` with each match as it's
+/// found, in the order of the entries. Entries that can't be read are skipped. Each body is held
+/// in memory while it's searched. Returns the number of matches.
+///
+/// Version 3.0 caches aren't supported, as their entry records don't keep the key.
+pub fn search<F>(
+    cache: &ChromeCache,
+    pattern: &Regex,
+    options: &SearchOptions,
+    mut on_match: F,
+) -> CCPResult<usize>
+where
+    F: FnMut(SearchMatch) -> CCPResult<()>,
+{
+    if cache.version()? == CacheVersion::Version3_0 {
+        return Err(CCPError::UnsupportedVersion(
+            "version 3.0 caches can't be searched".to_string(),
+        ));
+    }
+
+    let mut found = 0;
+
+    for entry in cache.entries()? {
+        let Ok(key) = entry.key() else {
+            continue;
+        };
+        let address = entry.location().addr;
+
+        let mut haystacks = Vec::new();
+        if options.keys {
+            haystacks.push((SearchTarget::Key, None, key.as_bytes().to_vec()));
+        }
+        if options.headers {
+            let mut info = Vec::new();
+            if entry
+                .stream_reader(0)
+                .and_then(|mut reader| Ok(reader.read_to_end(&mut info)?))
+                .is_ok()
+            {
+                haystacks.push((SearchTarget::Headers, Some(0), info));
+            }
+        }
+        if options.bodies {
+            if let Ok(body) = body(&entry) {
+                haystacks.push((SearchTarget::Body, Some(1), body));
+            }
+        }
+
+        for (target, stream, haystack) in haystacks {
+            for matched in pattern.find_iter(&haystack) {
+                found += 1;
+                on_match(SearchMatch {
+                    address,
+                    key: key.clone(),
+                    target,
+                    stream,
+                    offset: matched.start(),
+                    matched: matched.as_bytes().to_vec(),
+                })?;
+            }
+        }
+    }
+
+    Ok(found)
+}
+
+#[test]
+fn test_literal_pattern() {
+    let pattern = literal_pattern(&[0x89, b'P', b'N', b'G', b'.']).unwrap();
+    assert!(pattern.is_match(b"xx\x89PNG.\r\n"));
+    assert!(!pattern.is_match(b"xx\x89PNGx"));
+    assert_eq!(pattern.find(b"xx\x89PNG.").unwrap().start(), 2);
+}
@@ -0,0 +1,402 @@
+//! Identification of cached bodies by their leading bytes, for when the declared `Content-Type`
+//! is missing or wrong.
+use std::fmt::{self, Display};
+
+/// The number of leading bytes of a body needed to identify it.
+pub const SNIFF_LEN: usize = 512;
+
+/// The kinds of content that can be identified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ContentKind {
+    Html,
+    JavaScript,
+    Json,
+    Css,
+    Xml,
+    Svg,
+    Text,
+    Png,
+    Jpeg,
+    Gif,
+    WebP,
+    Avif,
+    Bmp,
+    Ico,
+    Mp4,
+    WebM,
+    Mp3,
+    Ogg,
+    Wav,
+    Woff,
+    Woff2,
+    Ttf,
+    Otf,
+    Pdf,
+    Wasm,
+    Gzip,
+    Zip,
+}
+
+/// Signatures that identify a kind from a fixed prefix.
+const SIGNATURES: &[(&[u8], ContentKind)] = &[
+    (b"\x89PNG\r\n\x1a\n", ContentKind::Png),
+    (b"\xff\xd8\xff", ContentKind::Jpeg),
+    (b"GIF87a", ContentKind::Gif),
+    (b"GIF89a", ContentKind::Gif),
+    (b"\0\0\x01\0", ContentKind::Ico),
+    (b"\x1a\x45\xdf\xa3", ContentKind::WebM),
+    (b"ID3", ContentKind::Mp3),
+    (b"OggS", ContentKind::Ogg),
+    (b"wOF2", ContentKind::Woff2),
+    (b"wOFF", ContentKind::Woff),
+    (b"\0\x01\0\0", ContentKind::Ttf),
+    (b"OTTO", ContentKind::Otf),
+    (b"%PDF-", ContentKind::Pdf),
+    (b"\0asm", ContentKind::Wasm),
+    (b"\x1f\x8b", ContentKind::Gzip),
+    (b"PK\x03\x04", ContentKind::Zip),
+];
+
+/// Prefixes that identify JavaScript, checked after leading whitespace.
+const JAVASCRIPT_PREFIXES: &[&str] = &[
+    "\"use strict\"",
+    "'use strict'",
+    "(function",
+    "!function",
+    "function",
+    "var ",
+    "let ",
+    "const ",
+    "import ",
+    "export ",
+    "window.",
+    "self.",
+];
+
+impl ContentKind {
+    /// The canonical MIME type of the kind.
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ContentKind::Html => "text/html",
+            ContentKind::JavaScript => "text/javascript",
+            ContentKind::Json => "application/json",
+            ContentKind::Css => "text/css",
+            ContentKind::Xml => "application/xml",
+            ContentKind::Svg => "image/svg+xml",
+            ContentKind::Text => "text/plain",
+            ContentKind::Png => "image/png",
+            ContentKind::Jpeg => "image/jpeg",
+            ContentKind::Gif => "image/gif",
+            ContentKind::WebP => "image/webp",
+            ContentKind::Avif => "image/avif",
+            ContentKind::Bmp => "image/bmp",
+            ContentKind::Ico => "image/x-icon",
+            ContentKind::Mp4 => "video/mp4",
+            ContentKind::WebM => "video/webm",
+            ContentKind::Mp3 => "audio/mpeg",
+            ContentKind::Ogg => "audio/ogg",
+            ContentKind::Wav => "audio/wav",
+            ContentKind::Woff => "font/woff",
+            ContentKind::Woff2 => "font/woff2",
+            ContentKind::Ttf => "font/ttf",
+            ContentKind::Otf => "font/otf",
+            ContentKind::Pdf => "application/pdf",
+            ContentKind::Wasm => "application/wasm",
+            ContentKind::Gzip => "application/gzip",
+            ContentKind::Zip => "application/zip",
+        }
+    }
+
+    /// The conventional file extension of the kind.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ContentKind::Html => "html",
+            ContentKind::JavaScript => "js",
+            ContentKind::Json => "json",
+            ContentKind::Css => "css",
+            ContentKind::Xml => "xml",
+            ContentKind::Svg => "svg",
+            ContentKind::Text => "txt",
+            ContentKind::Png => "png",
+            ContentKind::Jpeg => "jpg",
+            ContentKind::Gif => "gif",
+            ContentKind::WebP => "webp",
+            ContentKind::Avif => "avif",
+            ContentKind::Bmp => "bmp",
+            ContentKind::Ico => "ico",
+            ContentKind::Mp4 => "mp4",
+            ContentKind::WebM => "webm",
+            ContentKind::Mp3 => "mp3",
+            ContentKind::Ogg => "ogg",
+            ContentKind::Wav => "wav",
+            ContentKind::Woff => "woff",
+            ContentKind::Woff2 => "woff2",
+            ContentKind::Ttf => "ttf",
+            ContentKind::Otf => "otf",
+            ContentKind::Pdf => "pdf",
+            ContentKind::Wasm => "wasm",
+            ContentKind::Gzip => "gz",
+            ContentKind::Zip => "zip",
+        }
+    }
+
+    /// The kind of a declared MIME type, ignoring any parameters (e.g. `charset`).
+    pub fn from_mime_type(mime_type: &str) -> Option<ContentKind> {
+        let essence = mime_type.split(';').next()?.trim().to_ascii_lowercase();
+
+        Some(match essence.as_str() {
+            "text/html" | "application/xhtml+xml" => ContentKind::Html,
+            "text/javascript"
+            | "application/javascript"
+            | "application/x-javascript"
+            | "application/ecmascript"
+            | "text/ecmascript" => ContentKind::JavaScript,
+            "application/json" | "text/json" | "application/manifest+json" => ContentKind::Json,
+            "text/css" => ContentKind::Css,
+            "text/xml" | "application/xml" => ContentKind::Xml,
+            "image/svg+xml" => ContentKind::Svg,
+            "text/plain" => ContentKind::Text,
+            "image/png" => ContentKind::Png,
+            "image/jpeg" | "image/jpg" => ContentKind::Jpeg,
+            "image/gif" => ContentKind::Gif,
+            "image/webp" => ContentKind::WebP,
+            "image/avif" => ContentKind::Avif,
+            "image/bmp" => ContentKind::Bmp,
+            "image/x-icon" | "image/vnd.microsoft.icon" => ContentKind::Ico,
+            "video/mp4" | "audio/mp4" => ContentKind::Mp4,
+            "video/webm" | "audio/webm" => ContentKind::WebM,
+            "audio/mpeg" | "audio/mp3" => ContentKind::Mp3,
+            "audio/ogg" | "video/ogg" => ContentKind::Ogg,
+            "audio/wav" | "audio/x-wav" | "audio/wave" => ContentKind::Wav,
+            "font/woff" | "application/font-woff" => ContentKind::Woff,
+            "font/woff2" => ContentKind::Woff2,
+            "font/ttf" | "font/sfnt" | "application/x-font-ttf" => ContentKind::Ttf,
+            "font/otf" => ContentKind::Otf,
+            "application/pdf" => ContentKind::Pdf,
+            "application/wasm" => ContentKind::Wasm,
+            "application/gzip" | "application/x-gzip" => ContentKind::Gzip,
+            "application/zip" => ContentKind::Zip,
+            _ => return None,
+        })
+    }
+
+    /// Whether the kind is text, which can only be told apart heuristically.
+    pub fn is_text(&self) -> bool {
+        matches!(
+            self,
+            ContentKind::Html
+                | ContentKind::JavaScript
+                | ContentKind::Json
+                | ContentKind::Css
+                | ContentKind::Xml
+                | ContentKind::Svg
+                | ContentKind::Text
+        )
+    }
+}
+
+impl Display for ContentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.mime_type())
+    }
+}
+
+/// Identify the ISO base media formats (MP4, AVIF) from the brand of their `ftyp` box.
+fn sniff_ftyp(data: &[u8]) -> Option<ContentKind> {
+    if data.len() < 12 || &data[4..8] != b"ftyp" {
+        return None;
+    }
+
+    match &data[8..12] {
+        b"avif" | b"avis" => Some(ContentKind::Avif),
+        _ => Some(ContentKind::Mp4),
+    }
+}
+
+/// Identify BMP images from their `BITMAPFILEHEADER`, whose `BM` signature alone is too short to
+/// tell them from text: its reserved fields are zero and it's followed by the size of one of the
+/// known DIB headers.
+fn sniff_bmp(data: &[u8]) -> Option<ContentKind> {
+    if data.len() < 18 || &data[0..2] != b"BM" || data[6..10] != [0; 4] {
+        return None;
+    }
+
+    let dib_header_size = u32::from_le_bytes([data[14], data[15], data[16], data[17]]);
+    matches!(dib_header_size, 12 | 40 | 56 | 108 | 124).then_some(ContentKind::Bmp)
+}
+
+/// Identify the RIFF formats (WebP, WAV) from their form type.
+fn sniff_riff(data: &[u8]) -> Option<ContentKind> {
+    if data.len() < 12 || &data[0..4] != b"RIFF" {
+        return None;
+    }
+
+    match &data[8..12] {
+        b"WEBP" => Some(ContentKind::WebP),
+        b"WAVE" => Some(ContentKind::Wav),
+        _ => None,
+    }
+}
+
+/// Identify text content heuristically from how it starts.
+fn sniff_text(data: &[u8]) -> Option<ContentKind> {
+    let data = &data[..data.len().min(SNIFF_LEN)];
+    // A body cut off mid character is still text.
+    let text = match std::str::from_utf8(data) {
+        Ok(text) => text,
+        Err(err) if err.error_len().is_none() => {
+            std::str::from_utf8(&data[..err.valid_up_to()]).ok()?
+        }
+        Err(_) => return None,
+    };
+    if text.contains('\0') {
+        return None;
+    }
+
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    let lower = text.to_ascii_lowercase();
+
+    if lower.starts_with("<!doctype html")
+        || ["<html", "<head", "<body", "<script", "<meta", "<title"]
+            .iter()
+            .any(|tag| lower.starts_with(tag))
+    {
+        return Some(ContentKind::Html);
+    }
+    if lower.starts_with("<svg") || (lower.starts_with("<?xml") && lower.contains("<svg")) {
+        return Some(ContentKind::Svg);
+    }
+    if lower.starts_with("<?xml") {
+        return Some(ContentKind::Xml);
+    }
+
+    let mut chars = text.chars().filter(|c| !c.is_whitespace());
+    if let (Some(first), Some(second)) = (chars.next(), chars.next()) {
+        if (first == '{' && matches!(second, '"' | '}'))
+            || (first == '['
+                && (matches!(second, '"' | '{' | '[' | ']' | '-') || second.is_ascii_digit()))
+        {
+            return Some(ContentKind::Json);
+        }
+    }
+
+    if JAVASCRIPT_PREFIXES
+        .iter()
+        .any(|prefix| text.starts_with(prefix))
+    {
+        return Some(ContentKind::JavaScript);
+    }
+    if lower.starts_with("@charset") || lower.starts_with("@import") || lower.starts_with(":root") {
+        return Some(ContentKind::Css);
+    }
+
+    Some(ContentKind::Text)
+}
+
+/// Identify a body from its first bytes (ideally at least [`SNIFF_LEN`] of them, after undoing
+/// any `Content-Encoding`). Binary formats are identified by their signatures, text formats
+/// heuristically, falling back to plain text. Returns `None` for empty or unidentified binary
+/// bodies.
+pub fn sniff(data: &[u8]) -> Option<ContentKind> {
+    if data.is_empty() {
+        return None;
+    }
+
+    SIGNATURES
+        .iter()
+        .find(|(signature, _)| data.starts_with(signature))
+        .map(|(_, kind)| *kind)
+        .or_else(|| sniff_bmp(data))
+        .or_else(|| sniff_ftyp(data))
+        .or_else(|| sniff_riff(data))
+        .or_else(|| sniff_text(data))
+}
+
+/// The declared and sniffed types of a body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ContentTypes {
+    /// The `Content-Type` header of the response, if any.
+    pub declared: Option<String>,
+    /// The kind of the declared type, if it's known.
+    pub declared_kind: Option<ContentKind>,
+    /// The kind identified from the body.
+    pub sniffed: Option<ContentKind>,
+}
+
+impl ContentTypes {
+    /// Compare the declared type of a body with the type sniffed from its first bytes.
+    pub fn new(declared: Option<&str>, data: &[u8]) -> ContentTypes {
+        ContentTypes {
+            declared: declared.map(str::to_string),
+            declared_kind: declared.and_then(ContentKind::from_mime_type),
+            sniffed: sniff(data),
+        }
+    }
+
+    /// Whether the body doesn't look like its declared type. Text is only told apart
+    /// heuristically, so only a declared binary type or a sniffed binary type can mismatch, and
+    /// text declared as plain text (or sniffed as plain text) matches any text kind.
+    pub fn is_mismatch(&self) -> bool {
+        let (Some(declared), Some(sniffed)) = (self.declared_kind, self.sniffed) else {
+            return false;
+        };
+        if declared == sniffed {
+            return false;
+        }
+
+        match (declared.is_text(), sniffed.is_text()) {
+            (true, true) => {
+                declared != ContentKind::Text
+                    && sniffed != ContentKind::Text
+                    && !matches!(
+                        (declared, sniffed),
+                        (ContentKind::Xml, ContentKind::Svg)
+                            | (ContentKind::Html, ContentKind::Xml)
+                    )
+            }
+            _ => true,
+        }
+    }
+
+    /// The best guess at the type of the body: the sniffed type when the body clearly isn't what
+    /// it's declared as, and otherwise the declared type.
+    pub fn best_kind(&self) -> Option<ContentKind> {
+        if self.is_mismatch() {
+            self.sniffed
+        } else {
+            self.declared_kind.or(self.sniffed)
+        }
+    }
+}
+
+#[test]
+fn test_sniff() {
+    assert_eq!(sniff(b"\x89PNG\r\n\x1a\n\0\0"), Some(ContentKind::Png));
+    assert_eq!(sniff(b"RIFF\0\0\0\0WEBPVP8 "), Some(ContentKind::WebP));
+    assert_eq!(sniff(b"\0\0\0\x20ftypisom\0\0"), Some(ContentKind::Mp4));
+    assert_eq!(sniff(b"wOF2\0\x01\0\0"), Some(ContentKind::Woff2));
+    assert_eq!(
+        sniff(b"BM\x36\x00\x0c\x00\0\0\0\0\x36\0\0\0\x28\0\0\0"),
+        Some(ContentKind::Bmp)
+    );
+    assert_eq!(
+        sniff(b"BMW owners club newsletter"),
+        Some(ContentKind::Text)
+    );
+    assert_eq!(sniff(b"\0asm\x01\0\0\0"), Some(ContentKind::Wasm));
+    assert_eq!(sniff(b"\n<!DOCTYPE html><html>"), Some(ContentKind::Html));
+    assert_eq!(sniff(b"{\"a\": 1}"), Some(ContentKind::Json));
+    assert_eq!(sniff(b"(function(){})()"), Some(ContentKind::JavaScript));
+    assert_eq!(sniff(b"hello"), Some(ContentKind::Text));
+    assert_eq!(sniff(b"\xde\xad\xbe\xef\0"), None);
+    assert_eq!(sniff(b""), None);
+
+    let types = ContentTypes::new(Some("image/jpeg"), b"\x89PNG\r\n\x1a\n");
+    assert!(types.is_mismatch());
+    assert_eq!(types.best_kind(), Some(ContentKind::Png));
+    assert!(!ContentTypes::new(Some("text/html; charset=utf-8"), b"<html>").is_mismatch());
+    assert!(!ContentTypes::new(Some("application/javascript"), b"/* x */").is_mismatch());
+    assert!(ContentTypes::new(Some("application/json"), b"<!doctype html>").is_mismatch());
+}
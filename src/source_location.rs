@@ -0,0 +1,52 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use crate::{block_file::BLOCK_HEADER_SIZE, cache_address::FileType, CCPResult, CacheAddr};
+
+/// The physical source of a parsed structure: the file it was read from, the byte offset within
+/// that file, and the cache address that led to it.
+#[derive(Debug, Clone)]
+pub struct SourceLocation {
+    /// The `data_N` block file or `f_XXXXXX` external file holding the structure.
+    pub path: PathBuf,
+    /// The byte offset of the structure within the file.
+    pub offset: usize,
+    /// The first block of the structure within a block file. External files aren't split into
+    /// blocks.
+    pub block: Option<u32>,
+    pub addr: CacheAddr,
+}
+
+impl SourceLocation {
+    /// Locate the data a cache address points to within the cache directory.
+    pub fn of(addr: CacheAddr, cache_path: &Path) -> CCPResult<SourceLocation> {
+        let path = cache_path.join(addr.file_name());
+
+        match addr.file_type() {
+            FileType::External => Ok(SourceLocation {
+                path,
+                offset: 0,
+                block: None,
+                addr,
+            }),
+            file_type => Ok(SourceLocation {
+                path,
+                offset: BLOCK_HEADER_SIZE + addr.start_block() as usize * file_type.block_size()?,
+                block: Some(addr.start_block()),
+                addr,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} @ 0x{:x}", self.path.display(), self.offset)?;
+        if let Some(block) = self.block {
+            write!(f, " (block {})", block)?;
+        }
+        write!(f, " [addr 0x{:08x}]", self.addr.value)
+    }
+}
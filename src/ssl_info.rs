@@ -0,0 +1,192 @@
+//! The TLS details chrome persists with responses fetched over HTTPS: the certificate chain the
+//! server presented and how the connection was negotiated.
+//!
+//! See: https://chromium.googlesource.com/chromium/src/net/+/refs/heads/main/ssl/ssl_info.h
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::time::WindowsEpochMicroseconds;
+
+const PEM_LINE_LEN: usize = 64;
+
+const CONNECTION_VERSION_SHIFT: u32 = 20;
+const CONNECTION_VERSION_MASK: i32 = 0x7;
+const CONNECTION_CIPHER_SUITE_MASK: i32 = 0xffff;
+
+/// `CERT_STATUS_*` bits that mark certificate errors.
+const CERT_STATUS_ALL_ERRORS: u32 = 0xff00ffff;
+
+/// The names of the `CERT_STATUS_*` flags, by bit.
+///
+/// See: https://chromium.googlesource.com/chromium/src/net/+/refs/heads/main/cert/cert_status_flags_list.h
+const CERT_STATUS_FLAGS: &[(u32, &str)] = &[
+    (1 << 0, "COMMON_NAME_INVALID"),
+    (1 << 1, "DATE_INVALID"),
+    (1 << 2, "AUTHORITY_INVALID"),
+    (1 << 4, "NO_REVOCATION_MECHANISM"),
+    (1 << 5, "UNABLE_TO_CHECK_REVOCATION"),
+    (1 << 6, "REVOKED"),
+    (1 << 7, "INVALID"),
+    (1 << 8, "WEAK_SIGNATURE_ALGORITHM"),
+    (1 << 10, "NON_UNIQUE_NAME"),
+    (1 << 11, "WEAK_KEY"),
+    (1 << 13, "PINNED_KEY_MISSING"),
+    (1 << 14, "NAME_CONSTRAINT_VIOLATION"),
+    (1 << 15, "VALIDITY_TOO_LONG"),
+    (1 << 16, "IS_EV"),
+    (1 << 17, "REV_CHECKING_ENABLED"),
+    (1 << 19, "SHA1_SIGNATURE_PRESENT"),
+    (1 << 20, "CT_COMPLIANCE_FAILED"),
+];
+
+/// A signed certificate timestamp the server provided, proving the certificate was logged to a
+/// certificate transparency log.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SignedCertificateTimestamp {
+    /// The result of verifying the SCT.
+    pub status: u16,
+    pub version: i32,
+    /// Where the SCT came from: embedded in the certificate, the TLS extension or OCSP.
+    pub origin: i32,
+    pub log_id: Vec<u8>,
+    pub timestamp: WindowsEpochMicroseconds,
+    pub log_description: String,
+}
+
+/// The TLS details of the connection a response was fetched over.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SslInfo {
+    /// The certificate chain the server presented, as DER, starting with the leaf certificate.
+    pub certificates: Vec<Vec<u8>>,
+    /// The `CERT_STATUS_*` flags from verifying the certificate.
+    pub cert_status: Option<u32>,
+    /// The negotiated TLS version and cipher suite, as packed by `SSL_CONNECTION_*`.
+    pub connection_status: Option<i32>,
+    /// The IANA TLS NamedGroup used for key exchange.
+    pub key_exchange_group: Option<i32>,
+    /// The IANA TLS SignatureScheme the server signed the handshake with.
+    pub peer_signature_algorithm: Option<i32>,
+    pub signed_certificate_timestamps: Vec<SignedCertificateTimestamp>,
+}
+
+impl SslInfo {
+    /// The certificate chain as PEM, starting with the leaf certificate.
+    pub fn certificates_pem(&self) -> String {
+        let mut pem = String::new();
+
+        for certificate in &self.certificates {
+            pem.push_str("-----BEGIN CERTIFICATE-----\n");
+            let encoded = STANDARD.encode(certificate);
+            for line in encoded.as_bytes().chunks(PEM_LINE_LEN) {
+                pem.push_str(&String::from_utf8_lossy(line));
+                pem.push('\n');
+            }
+            pem.push_str("-----END CERTIFICATE-----\n");
+        }
+
+        pem
+    }
+
+    /// The names of the `CERT_STATUS_*` flags that are set.
+    pub fn cert_status_names(&self) -> Vec<&'static str> {
+        let status = self.cert_status.unwrap_or_default();
+        CERT_STATUS_FLAGS
+            .iter()
+            .filter(|(flag, _)| status & flag != 0)
+            .map(|(_, name)| *name)
+            .collect()
+    }
+
+    /// Whether verifying the certificate found errors.
+    pub fn has_cert_errors(&self) -> bool {
+        self.cert_status.unwrap_or_default() & CERT_STATUS_ALL_ERRORS != 0
+    }
+
+    /// The negotiated TLS version, e.g. `TLS 1.3`.
+    pub fn tls_version(&self) -> Option<&'static str> {
+        let status = self.connection_status?;
+        Some(
+            match (status >> CONNECTION_VERSION_SHIFT) & CONNECTION_VERSION_MASK {
+                1 => "SSL 2.0",
+                2 => "SSL 3.0",
+                3 => "TLS 1.0",
+                4 => "TLS 1.1",
+                5 => "TLS 1.2",
+                6 => "TLS 1.3",
+                7 => "QUIC",
+                _ => return None,
+            },
+        )
+    }
+
+    /// The IANA number of the negotiated cipher suite, e.g. `0x1301` for `TLS_AES_128_GCM_SHA256`.
+    pub fn cipher_suite(&self) -> Option<u16> {
+        self.connection_status
+            .map(|status| (status & CONNECTION_CIPHER_SUITE_MASK) as u16)
+    }
+
+    /// The name of the key exchange group, e.g. `X25519`.
+    pub fn key_exchange_group_name(&self) -> Option<&'static str> {
+        Some(match self.key_exchange_group? {
+            23 => "P-256",
+            24 => "P-384",
+            25 => "P-521",
+            29 => "X25519",
+            30 => "X448",
+            4588 => "X25519MLKEM768",
+            25497 => "X25519Kyber768Draft00",
+            _ => return None,
+        })
+    }
+
+    /// The name of the signature algorithm, e.g. `rsa_pss_rsae_sha256`.
+    pub fn peer_signature_algorithm_name(&self) -> Option<&'static str> {
+        Some(match self.peer_signature_algorithm? {
+            0x0201 => "rsa_pkcs1_sha1",
+            0x0203 => "ecdsa_sha1",
+            0x0401 => "rsa_pkcs1_sha256",
+            0x0501 => "rsa_pkcs1_sha384",
+            0x0601 => "rsa_pkcs1_sha512",
+            0x0403 => "ecdsa_secp256r1_sha256",
+            0x0503 => "ecdsa_secp384r1_sha384",
+            0x0603 => "ecdsa_secp521r1_sha512",
+            0x0804 => "rsa_pss_rsae_sha256",
+            0x0805 => "rsa_pss_rsae_sha384",
+            0x0806 => "rsa_pss_rsae_sha512",
+            0x0807 => "ed25519",
+            0x0808 => "ed448",
+            _ => return None,
+        })
+    }
+}
+
+#[test]
+fn test_ssl_info() {
+    let info = SslInfo {
+        certificates: vec![vec![0x30; 50], vec![0x30, 0x82]],
+        cert_status: Some((1 << 1) | (1 << 17)),
+        connection_status: Some((6 << 20) | 0x1301),
+        key_exchange_group: Some(29),
+        peer_signature_algorithm: Some(0x0804),
+        signed_certificate_timestamps: Vec::new(),
+    };
+
+    let pem = info.certificates_pem();
+    assert_eq!(pem.matches("-----BEGIN CERTIFICATE-----\n").count(), 2);
+    assert!(pem.lines().all(|line| line.len() <= PEM_LINE_LEN));
+    assert!(pem.contains("\nMII=\n"));
+
+    assert_eq!(
+        info.cert_status_names(),
+        ["DATE_INVALID", "REV_CHECKING_ENABLED"]
+    );
+    assert!(info.has_cert_errors());
+    assert_eq!(info.tls_version(), Some("TLS 1.3"));
+    assert_eq!(info.cipher_suite(), Some(0x1301));
+    assert_eq!(info.key_exchange_group_name(), Some("X25519"));
+    assert_eq!(
+        info.peer_signature_algorithm_name(),
+        Some("rsa_pss_rsae_sha256")
+    );
+}
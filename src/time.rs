@@ -1,5 +1,5 @@
 use chrono::{DateTime, Local, Utc};
-use zerocopy::{FromBytes, FromZeroes};
+use zerocopy::{AsBytes, FromBytes, FromZeroes};
 
 use crate::{CCPError, CCPResult};
 
@@ -10,10 +10,20 @@ const WIN_TO_UNIX_EPOCH_DIFF_MICROSEC: u64 = WIN_TO_UNIX_EPOCH_DELTA_SEC * MICRO
 
 /// Represents a time in microseconds since the Windows epoch (1601-01-01 00:00:00 UTC) (used in
 /// the chrome cache format).
-#[derive(Debug, FromZeroes, FromBytes, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, FromZeroes, FromBytes, AsBytes, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(C)]
 pub struct WindowsEpochMicroseconds(u64);
 
 impl WindowsEpochMicroseconds {
+    pub fn new(microseconds: u64) -> WindowsEpochMicroseconds {
+        WindowsEpochMicroseconds(microseconds)
+    }
+
+    /// The raw number of microseconds since the Windows epoch.
+    pub fn as_micros(&self) -> u64 {
+        self.0
+    }
+
     pub fn into_datetime_utc(self) -> CCPResult<DateTime<Utc>> {
         let windows_micro_seconds: u64 = self.0;
 
@@ -33,6 +43,18 @@ impl WindowsEpochMicroseconds {
     }
 }
 
+/// Serialized as an RFC 3339 timestamp, or `null` when the value can't be represented (e.g., an
+/// unset time of 0).
+#[cfg(feature = "serde")]
+impl serde::Serialize for WindowsEpochMicroseconds {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.into_datetime_utc() {
+            Ok(time) => serializer.serialize_str(&time.to_rfc3339()),
+            Err(_) => serializer.serialize_none(),
+        }
+    }
+}
+
 #[cfg(test)]
 #[test]
 fn test_windows_epoch_microseconds() {
@@ -0,0 +1,107 @@
+//! The digest chrome stores for responses with a `Vary` header, of the request headers the
+//! response varies on, which decides whether the cached response can be served for a request.
+//!
+//! See: https://chromium.googlesource.com/chromium/src/net/+/refs/heads/main/http/http_vary_data.cc
+use md5::{Digest, Md5};
+
+use crate::{hash::to_hex, response_info::HttpHeaders};
+
+/// The MD5 digest of the values of the request headers named by a response's `Vary` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HttpVaryData {
+    pub request_digest: [u8; 16],
+}
+
+impl HttpVaryData {
+    /// Compute the vary data chrome would store for a response with the given headers, fetched
+    /// with the given request headers. Returns `None` when the response doesn't vary on any
+    /// request header. For a response that varies on everything (`Vary: *`), chrome stores an
+    /// all-zero digest, which never matches a request.
+    pub fn compute(
+        response_headers: &HttpHeaders,
+        request_headers: &[(&str, &str)],
+    ) -> Option<HttpVaryData> {
+        let names = vary_header_names(response_headers);
+        if names.is_empty() {
+            return None;
+        }
+        if names.contains(&"*") {
+            return Some(HttpVaryData {
+                request_digest: [0; 16],
+            });
+        }
+
+        let mut md5 = Md5::new();
+        for name in names {
+            let value = request_headers
+                .iter()
+                .find(|(header, _)| header.eq_ignore_ascii_case(name))
+                .map_or("", |(_, value)| value);
+            // The separator can't appear in a header value, so that e.g. "fo" + "o" and
+            // "foo" + "" don't collide.
+            md5.update(value.as_bytes());
+            md5.update(b"\n");
+        }
+
+        Some(HttpVaryData {
+            request_digest: md5.finalize().into(),
+        })
+    }
+
+    /// Whether a request with the given headers would be served the cached response with the
+    /// given headers, i.e. whether it has the same values for the headers the response varies on.
+    /// A response that varies on everything never matches.
+    pub fn matches_request(
+        &self,
+        response_headers: &HttpHeaders,
+        request_headers: &[(&str, &str)],
+    ) -> bool {
+        !self.varies_on_everything()
+            && HttpVaryData::compute(response_headers, request_headers) == Some(*self)
+    }
+
+    /// Whether this is the all-zero digest chrome stores for `Vary: *`.
+    pub fn varies_on_everything(&self) -> bool {
+        self.request_digest == [0; 16]
+    }
+
+    /// The digest as lowercase hex.
+    pub fn to_hex(&self) -> String {
+        to_hex(&self.request_digest)
+    }
+}
+
+/// The names of the request headers listed across every `Vary` header, in order.
+pub fn vary_header_names(headers: &HttpHeaders) -> Vec<&str> {
+    headers
+        .get_all("vary")
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+#[test]
+fn test_vary_data() {
+    let headers = HttpHeaders::from_raw(b"HTTP/1.1 200 OK\0Vary: Accept-Encoding, Origin\0\0");
+    let request = [
+        ("accept-encoding", "gzip, br"),
+        ("User-Agent", "Mozilla/5.0"),
+    ];
+
+    let vary = HttpVaryData::compute(&headers, &request).unwrap();
+    // md5("gzip, br\n" + "\n"), as the request has no `Origin`.
+    assert_eq!(vary.to_hex(), "4ee98df40ad252bbf2322d5561123518");
+    assert!(vary.matches_request(&headers, &request));
+    assert!(!vary.matches_request(&headers, &[("Accept-Encoding", "gzip")]));
+
+    let vary_all = HttpHeaders::from_raw(b"HTTP/1.1 200 OK\0Vary: *\0\0");
+    let vary = HttpVaryData::compute(&vary_all, &request).unwrap();
+    assert!(vary.varies_on_everything());
+    assert!(!vary.matches_request(&vary_all, &request));
+    assert!(!vary.matches_request(&vary_all, &[]));
+
+    let no_vary = HttpHeaders::from_raw(b"HTTP/1.1 200 OK\0\0");
+    assert_eq!(HttpVaryData::compute(&no_vary, &request), None);
+}
@@ -0,0 +1,81 @@
+//! Reads the headers, allocation maps and chains of the block files of caches written to disk.
+mod common;
+
+use std::fs;
+
+use chrome_cache_parser::{CCPError, FileType};
+use common::*;
+
+#[test]
+fn test_block_file_allocations() {
+    let fixture = sample_cache();
+    let files = fixture.open().block_files().unwrap();
+    assert_eq!(files.len(), 4);
+
+    for file in files {
+        let header = file.header().unwrap();
+        let block_size = header.entry_size as usize;
+        // The fixture allocates blocks contiguously from the start of each file.
+        let stored_blocks =
+            (fs::metadata(file.path()).unwrap().len() as usize - BLOCK_HEADER_SIZE) / block_size;
+
+        let blocks = header.blocks().collect::<Vec<_>>();
+        assert_eq!(blocks.len(), header.max_entries as usize);
+        let used = blocks.iter().filter(|block| block.used).count();
+        assert_eq!(used, stored_blocks, "{}", file.path().display());
+        assert!(blocks
+            .iter()
+            .all(|block| block.used == (block.block < stored_blocks)));
+
+        assert!(!header.allocation_map.is_used(stored_blocks));
+        assert!(!header.allocation_map.is_used(usize::MAX));
+    }
+}
+
+/// Set the `this_file` and `next_file` fields of a block file's header.
+fn link(fixture: &TestCache, number: u32, this_file: i16, next_file: i16) {
+    let path = fixture.path.join(format!("data_{}", number));
+    let mut data = fs::read(&path).unwrap();
+    data[8..10].copy_from_slice(&this_file.to_le_bytes());
+    data[10..12].copy_from_slice(&next_file.to_le_bytes());
+    fs::write(path, data).unwrap();
+}
+
+#[test]
+fn test_block_file_chain() {
+    let fixture = sample_cache();
+    // data_1 continued by data_4.
+    fs::copy(fixture.path.join("data_1"), fixture.path.join("data_4")).unwrap();
+    link(&fixture, 1, 1, 4);
+    link(&fixture, 4, 4, 0);
+
+    let cache = fixture.open();
+    let chain = cache.block_file_chain(FileType::Block256).unwrap();
+    let numbers = chain
+        .iter()
+        .map(|file| file.header().unwrap().this_file)
+        .collect::<Vec<_>>();
+    assert_eq!(numbers, [1, 4]);
+    assert_eq!(chain[1].path(), fixture.path.join("data_4"));
+    assert_eq!(cache.block_files().unwrap().len(), 5);
+
+    link(&fixture, 4, 4, 1);
+    let err = fixture
+        .open()
+        .block_file_chain(FileType::Block256)
+        .err()
+        .unwrap();
+    assert!(
+        matches!(&err, CCPError::InvalidData(message) if message.contains("loops back to data_1"))
+    );
+
+    link(&fixture, 4, 5, 0);
+    let err = fixture
+        .open()
+        .block_file_chain(FileType::Block256)
+        .err()
+        .unwrap();
+    assert!(
+        matches!(&err, CCPError::InvalidData(message) if message.contains("data_4 claims to be data_5"))
+    );
+}
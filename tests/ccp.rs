@@ -0,0 +1,94 @@
+//! Runs the `ccp` binary against caches written to disk, checking its exit codes.
+#![cfg(feature = "cli")]
+mod common;
+
+use std::{
+    path::Path,
+    process::{Command, Output},
+};
+
+use common::*;
+
+fn ccp(path: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ccp"))
+        .arg("--path")
+        .arg(path)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_ccp() {
+    let fixture = sample_cache();
+
+    let output = ccp(&fixture.path, &["ls"]);
+    assert_eq!(output.status.code(), Some(0));
+    let listing = String::from_utf8(output.stdout).unwrap();
+    assert!(listing.contains(LOGO_KEY));
+
+    let output = ccp(&fixture.path, &["cat", LOGO_KEY]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(output.stdout, logo_body());
+
+    assert_eq!(ccp(&fixture.path, &["check"]).status.code(), Some(0));
+}
+
+#[test]
+fn test_ccp_exit_codes() {
+    let fixture = sample_cache();
+
+    let missing_key = ccp(&fixture.path, &["cat", "https://example.com/missing"]);
+    assert_eq!(missing_key.status.code(), Some(3));
+    assert_eq!(
+        ccp(&fixture.path, &["lookup", "https://example.com/missing"])
+            .status
+            .code(),
+        Some(3)
+    );
+
+    let missing_dir = fixture.path.join("missing");
+    assert_eq!(ccp(&missing_dir, &["ls"]).status.code(), Some(1));
+
+    assert_eq!(
+        ccp(&fixture.path, &["no-such-command"]).status.code(),
+        Some(2)
+    );
+
+    // An entry whose body is in a missing block file.
+    let mut entries = sample_entries();
+    let mut broken = FixtureEntry::new(
+        "https://example.com/broken",
+        vec![Vec::new(), b"x".to_vec()],
+    );
+    broken.stream_addrs.push((1, 0xa009_0001));
+    entries.push(broken);
+    let broken = build_cache(&entries, &[]);
+    let output = ccp(&broken.path, &["check"]);
+    assert_eq!(output.status.code(), Some(4));
+}
+
+#[test]
+fn test_ccp_v3() {
+    let fixture = v3_cache();
+
+    assert_eq!(ccp(&fixture.path, &["info"]).status.code(), Some(0));
+    let output = ccp(&fixture.path, &["ls"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("only supported by `ccp info`"));
+}
+
+#[test]
+fn test_ccp_discover() {
+    // Discovery doesn't open a cache, so it needs no `--path`.
+    let root = temp_dir("discover");
+    let output = Command::new(env!("CARGO_BIN_EXE_ccp"))
+        .arg("discover")
+        .arg(&root)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    std::fs::remove_dir_all(root).unwrap();
+}
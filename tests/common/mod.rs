@@ -0,0 +1,388 @@
+//! Builds small version 2.1 block file caches on disk for the integration tests.
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use chrome_cache_parser::{hash::super_fast_hash, pickle::PickleWriter, ChromeCache};
+use flate2::{write::GzEncoder, Compression};
+
+const INDEX_MAGIC: u32 = 0xc103cac3;
+const BLOCK_MAGIC: u32 = 0xc104cac3;
+pub const BLOCK_HEADER_SIZE: usize = 8192;
+const TABLE_LEN: usize = 256;
+const ENTRY_SIZE: usize = 96;
+
+/// When every fixture response was received: 2023-11-14T22:13:20Z.
+pub const RESPONSE_TIME: i64 = 1_700_000_000;
+
+/// Microseconds since the Windows epoch.
+pub fn windows_time(unix: i64) -> i64 {
+    (unix + 11_644_473_600) * 1_000_000
+}
+
+fn addr(file_type: u32, blocks: usize, file: u32, start: usize) -> u32 {
+    0x8000_0000 | file_type << 28 | ((blocks - 1) as u32) << 24 | file << 16 | start as u32
+}
+
+struct BlockFile {
+    number: u32,
+    block_size: usize,
+    blocks: Vec<u8>,
+    used: usize,
+    entries: i32,
+}
+
+impl BlockFile {
+    fn new(number: u32, block_size: usize) -> BlockFile {
+        BlockFile {
+            number,
+            block_size,
+            blocks: Vec::new(),
+            used: 0,
+            entries: 0,
+        }
+    }
+
+    /// Allocate enough contiguous blocks for `len` bytes, returning the first.
+    fn allocate(&mut self, len: usize) -> (usize, usize) {
+        let blocks = len.div_ceil(self.block_size).max(1);
+        assert!(blocks <= 4, "{} bytes don't fit in one allocation", len);
+        let start = self.used;
+        self.used += blocks;
+        self.entries += 1;
+        self.blocks.resize(self.used * self.block_size, 0);
+        (start, blocks)
+    }
+
+    fn store(&mut self, data: &[u8], file_type: u32) -> u32 {
+        let (start, blocks) = self.allocate(data.len());
+        let offset = start * self.block_size;
+        self.blocks[offset..offset + data.len()].copy_from_slice(data);
+        addr(file_type, blocks, self.number, start)
+    }
+
+    fn write(&self, path: &Path) {
+        let max_entries = (self.used as i32).max(1024);
+        let mut data = Vec::with_capacity(BLOCK_HEADER_SIZE + self.blocks.len());
+        for value in [BLOCK_MAGIC, 0x20000] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&(self.number as i16).to_le_bytes());
+        data.extend_from_slice(&0i16.to_le_bytes());
+        for value in [self.block_size as i32, self.entries, max_entries] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        // The empty counts, hints, updating flag and user data.
+        data.resize(80, 0);
+        let mut bitmap = vec![0u8; BLOCK_HEADER_SIZE - 80];
+        for block in 0..self.used {
+            bitmap[block / 8] |= 1 << (block % 8);
+        }
+        data.extend_from_slice(&bitmap);
+        data.extend_from_slice(&self.blocks);
+        fs::write(path.join(format!("data_{}", self.number)), data).unwrap();
+    }
+}
+
+/// A cache entry to write.
+pub struct FixtureEntry {
+    pub key: String,
+    pub streams: Vec<Vec<u8>>,
+    /// Stream addresses to store in place of the streams' real ones, e.g. to point into a missing
+    /// block file.
+    pub stream_addrs: Vec<(usize, u32)>,
+    /// The address of a long key to store in place of the inline key's, e.g. to point into a
+    /// missing block file.
+    pub long_key: u32,
+}
+
+impl FixtureEntry {
+    pub fn new(key: &str, streams: Vec<Vec<u8>>) -> FixtureEntry {
+        FixtureEntry {
+            key: key.to_string(),
+            streams,
+            stream_addrs: Vec::new(),
+            long_key: 0,
+        }
+    }
+}
+
+/// A cache written to a temporary directory, removed when dropped.
+pub struct TestCache {
+    pub path: PathBuf,
+}
+
+impl TestCache {
+    pub fn open(&self) -> ChromeCache {
+        ChromeCache::from_path(self.path.clone()).unwrap()
+    }
+}
+
+impl Drop for TestCache {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// A fresh, empty temporary directory.
+pub fn temp_dir(name: &str) -> PathBuf {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "ccp-{}-{}-{}",
+        name,
+        process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    let _ = fs::remove_dir_all(&path);
+    fs::create_dir_all(&path).unwrap();
+    path
+}
+
+/// Write a cache holding the given entries. `bad_table_addrs` are stored in otherwise empty
+/// buckets of the index table.
+pub fn build_cache(entries: &[FixtureEntry], bad_table_addrs: &[u32]) -> TestCache {
+    let path = temp_dir("cache");
+    let mut rankings = BlockFile::new(0, 36);
+    let mut block256 = BlockFile::new(1, 256);
+    let mut block1k = BlockFile::new(2, 1024);
+    let mut block4k = BlockFile::new(3, 4096);
+    let mut external = 0;
+    let mut table = [0u32; TABLE_LEN];
+
+    // Allocate the entries first so that they're in the first blocks of data_1.
+    let entry_addrs = entries
+        .iter()
+        .map(|entry| {
+            let (start, blocks) = block256.allocate(ENTRY_SIZE + entry.key.len() + 1);
+            addr(2, blocks, 1, start)
+        })
+        .collect::<Vec<_>>();
+
+    for (entry, entry_addr) in entries.iter().zip(&entry_addrs) {
+        let mut data_addrs = [0u32; 4];
+        let mut data_sizes = [0i32; 4];
+        for (stream, data) in entry.streams.iter().enumerate() {
+            if data.is_empty() {
+                continue;
+            }
+            data_sizes[stream] = data.len() as i32;
+            data_addrs[stream] = match data.len() {
+                0..=256 => block256.store(data, 2),
+                257..=1024 => block1k.store(data, 3),
+                1025..=16384 => block4k.store(data, 4),
+                _ => {
+                    external += 1;
+                    fs::write(path.join(format!("f_{:06x}", external)), data).unwrap();
+                    0x8000_0000 | external
+                }
+            };
+        }
+        for (stream, stream_addr) in &entry.stream_addrs {
+            data_addrs[*stream] = *stream_addr;
+        }
+
+        let mut node = Vec::new();
+        node.extend_from_slice(&windows_time(RESPONSE_TIME + 60).to_le_bytes());
+        node.extend_from_slice(&windows_time(RESPONSE_TIME + 30).to_le_bytes());
+        for value in [0, 0, *entry_addr, 0, 0] {
+            node.extend_from_slice(&value.to_le_bytes());
+        }
+        let rankings_addr = rankings.store(&node, 1);
+
+        let key = entry.key.as_bytes();
+        let hash = super_fast_hash(key);
+        let bucket = hash as usize & (TABLE_LEN - 1);
+
+        let mut record = Vec::new();
+        for value in [hash, 0, rankings_addr] {
+            record.extend_from_slice(&value.to_le_bytes());
+        }
+        // The reuse count, refetch count and state.
+        for value in [1i32, 0, 0] {
+            record.extend_from_slice(&value.to_le_bytes());
+        }
+        record.extend_from_slice(&windows_time(RESPONSE_TIME - 1).to_le_bytes());
+        record.extend_from_slice(&(key.len() as i32).to_le_bytes());
+        record.extend_from_slice(&entry.long_key.to_le_bytes());
+        for size in data_sizes {
+            record.extend_from_slice(&size.to_le_bytes());
+        }
+        for data_addr in data_addrs {
+            record.extend_from_slice(&data_addr.to_le_bytes());
+        }
+        record.resize(ENTRY_SIZE, 0);
+        record.extend_from_slice(key);
+        record.push(0);
+
+        let offset = (*entry_addr as usize & 0xffff) * 256;
+        block256.blocks[offset..offset + record.len()].copy_from_slice(&record);
+
+        // Chain entries that share a bucket through their `next` field.
+        if table[bucket] == 0 {
+            table[bucket] = *entry_addr;
+        } else {
+            let mut last = table[bucket];
+            loop {
+                let offset = (last as usize & 0xffff) * 256;
+                let next =
+                    u32::from_le_bytes(block256.blocks[offset + 4..offset + 8].try_into().unwrap());
+                if next == 0 {
+                    block256.blocks[offset + 4..offset + 8]
+                        .copy_from_slice(&entry_addr.to_le_bytes());
+                    break;
+                }
+                last = next;
+            }
+        }
+    }
+
+    for bad_addr in bad_table_addrs {
+        let bucket = (0..TABLE_LEN)
+            .rev()
+            .find(|bucket| table[*bucket] == 0)
+            .unwrap();
+        table[bucket] = *bad_addr;
+    }
+
+    for file in [&rankings, &block256, &block1k, &block4k] {
+        file.write(&path);
+    }
+
+    let mut index = Vec::new();
+    for value in [INDEX_MAGIC, 0x20001] {
+        index.extend_from_slice(&value.to_le_bytes());
+    }
+    for value in [entries.len() as i32, 0, external as i32, 0] {
+        index.extend_from_slice(&value.to_le_bytes());
+    }
+    index.extend_from_slice(&0u32.to_le_bytes());
+    for value in [TABLE_LEN as i32, 0, 0] {
+        index.extend_from_slice(&value.to_le_bytes());
+    }
+    index.extend_from_slice(&windows_time(RESPONSE_TIME - 86_400).to_le_bytes());
+    index.resize(368, 0);
+    for bucket in table {
+        index.extend_from_slice(&bucket.to_le_bytes());
+    }
+    fs::write(path.join("index"), index).unwrap();
+
+    TestCache { path }
+}
+
+/// Write an empty version 3.0 cache, for the commands that only read version 2 caches: an index
+/// holding the header and the first page of the bitmap, an empty main table and empty block files.
+pub fn v3_cache() -> TestCache {
+    let path = temp_dir("cache-v3");
+    let mut index = Vec::new();
+    for value in [INDEX_MAGIC, 0x30000] {
+        index.extend_from_slice(&value.to_le_bytes());
+    }
+    index.resize(4096, 0);
+    fs::write(path.join("index"), index).unwrap();
+    fs::write(path.join("index_tb1"), []).unwrap();
+    for (number, block_size) in [(1, 256), (2, 1024), (3, 4096), (5, 104), (6, 48)] {
+        BlockFile::new(number, block_size).write(&path);
+    }
+
+    TestCache { path }
+}
+
+/// The pickled response info of a response with the given status line and headers, received from
+/// `93.184.215.14:443` over HTTP/2.
+pub fn response_info(headers: &[&str]) -> Vec<u8> {
+    let mut raw = headers.join("\0");
+    raw.push_str("\0\0");
+
+    PickleWriter::new()
+        // The version, with the remote endpoint, ALPN and connection info.
+        .write_i32(3 | 1 << 17 | 1 << 18)
+        .write_i64(windows_time(RESPONSE_TIME - 1))
+        .write_i64(windows_time(RESPONSE_TIME))
+        .write_string(raw.as_bytes())
+        .write_string(b"93.184.215.14")
+        .write_u16(443)
+        .write_string(b"h2")
+        .write_i32(4)
+        .to_bytes()
+}
+
+pub fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+pub const PAGE_KEY: &str = "1/0/_dk_https://example.com https://example.com https://example.com/";
+pub const PAGE_URL: &str = "https://example.com/";
+pub const LOGO_KEY: &str = "https://example.com/logo.png";
+pub const SCRIPT_KEY: &str = "https://example.com/app.js";
+pub const REDIRECT_KEY: &str = "https://example.com/old";
+
+pub fn page_body() -> Vec<u8> {
+    let mut page = b"<!doctype html><html><head><title>Example</title></head><body>".to_vec();
+    page.extend_from_slice(&b"hello world ".repeat(200));
+    page.extend_from_slice(b"</body></html>");
+    page
+}
+
+pub fn logo_body() -> Vec<u8> {
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    png.resize(108, 0);
+    png
+}
+
+pub fn script_body() -> Vec<u8> {
+    b"function hello() { return \"secret-token-1234\"; }\n".repeat(50)
+}
+
+/// A cache with a gzipped page setting cookies, an image, a script stored out of line and a
+/// redirect.
+pub fn sample_cache() -> TestCache {
+    build_cache(&sample_entries(), &[])
+}
+
+pub fn sample_entries() -> Vec<FixtureEntry> {
+    vec![
+        FixtureEntry::new(
+            PAGE_KEY,
+            vec![
+                response_info(&[
+                    "HTTP/1.1 200 OK",
+                    "Content-Type: text/html; charset=utf-8",
+                    "Content-Encoding: gzip",
+                    "Cache-Control: max-age=600",
+                    "Set-Cookie: session=abc123; Domain=example.com; Path=/; Secure; HttpOnly",
+                    "Set-Cookie: theme=dark; Max-Age=3600",
+                ]),
+                gzip(&page_body()),
+            ],
+        ),
+        FixtureEntry::new(
+            LOGO_KEY,
+            vec![
+                response_info(&["HTTP/1.1 200 OK", "Content-Type: image/png"]),
+                logo_body(),
+            ],
+        ),
+        FixtureEntry::new(
+            SCRIPT_KEY,
+            vec![
+                response_info(&["HTTP/1.1 200 OK", "Content-Type: application/javascript"]),
+                script_body(),
+            ],
+        ),
+        FixtureEntry::new(
+            REDIRECT_KEY,
+            vec![response_info(&[
+                "HTTP/1.1 301 Moved Permanently",
+                "Location: https://example.com/",
+            ])],
+        ),
+    ]
+}
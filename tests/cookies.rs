@@ -0,0 +1,42 @@
+//! Gathers the cookies set by the responses of caches written to disk.
+mod common;
+
+use chrome_cache_parser::cookies::cached_cookies;
+use common::*;
+
+#[test]
+fn test_cached_cookies() {
+    let mut entries = sample_entries();
+    entries.push(FixtureEntry::new(
+        "https://example.com/broken",
+        vec![b"Set-Cookie: ignored=1".to_vec(), b"evidence".to_vec()],
+    ));
+    let fixture = build_cache(&entries, &[0xa009_0001]);
+
+    let cookies = cached_cookies(&fixture.open()).unwrap();
+    assert_eq!(cookies.len(), 2);
+    assert!(cookies.iter().all(|cookie| cookie.url == PAGE_URL));
+
+    let session = &cookies[0];
+    assert_eq!(session.cookie.name, "session");
+    assert_eq!(session.cookie.value, "abc123");
+    assert_eq!(
+        (session.domain.as_str(), session.host_only),
+        ("example.com", false)
+    );
+    assert_eq!(session.path, "/");
+    assert!(session.cookie.secure && session.cookie.http_only);
+    assert_eq!(session.expires, None);
+
+    // Host-only, and expiring an hour after the response was received.
+    let theme = &cookies[1];
+    assert_eq!(theme.cookie.name, "theme");
+    assert_eq!(
+        (theme.domain.as_str(), theme.host_only),
+        ("example.com", true)
+    );
+    assert_eq!(
+        theme.expires.map(|expires| expires.timestamp()),
+        Some(RESPONSE_TIME + 3600)
+    );
+}
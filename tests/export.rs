@@ -0,0 +1,331 @@
+//! Runs the exporters against caches written to disk.
+mod common;
+
+use std::fs;
+
+use chrome_cache_parser::export::extract::{
+    extract, ExtractOptions, HASH_MANIFEST_FILE_NAME, MANIFEST_FILE_NAME,
+};
+use common::*;
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn test_export_sqlite() {
+    use chrome_cache_parser::export::sqlite::{export_sqlite, SqliteExportOptions};
+
+    let mut entries = sample_entries();
+    // A body at the address of a rankings node, which can't hold stream data.
+    entries.push(FixtureEntry {
+        stream_addrs: vec![(1, 0x9000_0000)],
+        ..FixtureEntry::new(
+            "https://example.com/missing.css",
+            vec![response_info(&["HTTP/1.1 200 OK"]), b"body".to_vec()],
+        )
+    });
+    // An entry in a block file that doesn't exist.
+    let cache = build_cache(&entries, &[0xa009_0001]);
+    let out = temp_dir("sqlite");
+    let db = out.join("cache.sqlite");
+
+    let options = SqliteExportOptions {
+        include_bodies: true,
+    };
+    assert_eq!(export_sqlite(&cache.open(), &db, &options).unwrap(), 5);
+
+    let connection = rusqlite::Connection::open(&db).unwrap();
+    let count = |sql: &str| -> i64 { connection.query_row(sql, [], |row| row.get(0)).unwrap() };
+    assert_eq!(count("SELECT COUNT(*) FROM entries"), 5);
+    assert_eq!(count("SELECT COUNT(*) FROM bodies"), 3);
+    assert_eq!(
+        count("SELECT COUNT(*) FROM response_headers WHERE name = 'Set-Cookie'"),
+        2
+    );
+    let (host, port): (String, u16) = connection
+        .query_row(
+            "SELECT remote_host, remote_port FROM entries WHERE key = ?1",
+            [LOGO_KEY],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap();
+    assert_eq!((host.as_str(), port), ("93.184.215.14", 443));
+
+    drop(connection);
+    fs::remove_dir_all(out).unwrap();
+}
+
+#[test]
+fn test_extract() {
+    let mut entries = sample_entries();
+    entries.push(FixtureEntry::new(
+        "https://example.com/broken",
+        vec![b"not a pickle".to_vec(), b"evidence".to_vec()],
+    ));
+    entries.push(FixtureEntry::new(
+        "https://example.com/corrupt.js",
+        vec![
+            response_info(&["HTTP/1.1 200 OK", "Content-Encoding: gzip"]),
+            b"\x1f\x8b not really gzip".to_vec(),
+        ],
+    ));
+    // A body that fails to decode part way, once its file has been created.
+    let noise = (0u32..16384)
+        .flat_map(|i| i.wrapping_mul(2_654_435_761).to_le_bytes())
+        .collect::<Vec<_>>();
+    let truncated = gzip(&noise);
+    entries.push(FixtureEntry::new(
+        "https://example.com/truncated.txt",
+        vec![
+            response_info(&["HTTP/1.1 200 OK", "Content-Encoding: gzip"]),
+            truncated[..truncated.len() / 2].to_vec(),
+        ],
+    ));
+    let cache = build_cache(&entries, &[]);
+    let out = temp_dir("extract");
+
+    let report = extract(&cache.open(), &out, &ExtractOptions { raw: false }).unwrap();
+    assert_eq!(report.files.len(), 3);
+    assert_eq!(report.failures.len(), 3);
+    assert!(!out.join("example.com/truncated.txt").exists());
+
+    let page = fs::read(out.join("example.com/index.html")).unwrap();
+    assert_eq!(page, page_body());
+    assert_eq!(
+        fs::read(out.join("example.com/logo.png")).unwrap(),
+        logo_body()
+    );
+
+    let manifest = fs::read_to_string(out.join(MANIFEST_FILE_NAME)).unwrap();
+    assert_eq!(manifest.lines().count(), 4);
+    assert!(manifest.contains("example.com/index.html\t"));
+    assert!(manifest.contains("\t93.184.215.14:443\t"));
+
+    // The streams of the entries that failed to extract, without response info or with a
+    // corrupt or truncated body, are still digested.
+    let hashes = fs::read_to_string(out.join(HASH_MANIFEST_FILE_NAME)).unwrap();
+    for (address, _) in &report.failures {
+        let failed = format!("stream\t-\t0x{:08x}\t", address.value);
+        let failed_streams = hashes
+            .lines()
+            .filter(|line| line.starts_with(&failed))
+            .count();
+        assert_eq!(failed_streams, 2);
+    }
+    // A stream row for each non-empty stream, and a file row for each extracted body.
+    assert_eq!(
+        hashes
+            .lines()
+            .filter(|line| line.starts_with("stream\t"))
+            .count(),
+        13
+    );
+    assert_eq!(
+        hashes
+            .lines()
+            .filter(|line| line.starts_with("file\t"))
+            .count(),
+        3
+    );
+
+    fs::remove_dir_all(out).unwrap();
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_export_jsonl() {
+    use chrome_cache_parser::export::jsonl::write_jsonl;
+
+    let cache = build_cache(&sample_entries(), &[0xa009_0001]);
+    let mut out = Vec::new();
+    assert_eq!(write_jsonl(&cache.open(), &mut out).unwrap(), 4);
+
+    let lines = String::from_utf8(out).unwrap();
+    let entries = lines
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    let mut keys = entries
+        .iter()
+        .map(|entry| entry["key"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    keys.sort();
+    assert_eq!(keys, [PAGE_KEY, SCRIPT_KEY, LOGO_KEY, REDIRECT_KEY]);
+
+    let logo = entries
+        .iter()
+        .find(|entry| entry["key"] == LOGO_KEY)
+        .unwrap();
+    assert_eq!(logo["stream_sizes"][1], logo_body().len());
+    assert_eq!(logo["state"], "Normal");
+    assert_eq!(logo["creation_time"], "2023-11-14T22:13:19+00:00");
+    assert_eq!(logo["remote_endpoint"]["host"], "93.184.215.14");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_export_har() {
+    use base64::prelude::*;
+    use chrome_cache_parser::export::har::write_har;
+
+    let cache = build_cache(&sample_entries(), &[]);
+    let mut out = Vec::new();
+    write_har(&cache.open(), &mut out).unwrap();
+
+    let har = serde_json::from_slice::<serde_json::Value>(&out).unwrap();
+    let entries = har["log"]["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 4);
+    let entry = |url: &str| {
+        entries
+            .iter()
+            .find(|entry| entry["request"]["url"] == url)
+            .unwrap()
+    };
+
+    // The page is decoded from its gzip encoding, and its URL is taken from the partitioned key.
+    let page = &entry(PAGE_URL)["response"];
+    assert_eq!(page["status"], 200);
+    assert_eq!(page["content"]["size"], page_body().len());
+    assert_eq!(
+        page["content"]["text"].as_str().unwrap().as_bytes(),
+        page_body()
+    );
+    assert_eq!(entry(PAGE_URL)["_cacheKey"], PAGE_KEY);
+
+    // Binary bodies are base64 encoded.
+    let logo = &entry(LOGO_KEY)["response"]["content"];
+    assert_eq!(logo["encoding"], "base64");
+    let text = logo["text"].as_str().unwrap();
+    assert_eq!(BASE64_STANDARD.decode(text).unwrap(), logo_body());
+    assert_eq!(entry(LOGO_KEY)["serverIPAddress"], "93.184.215.14");
+
+    let redirect = &entry(REDIRECT_KEY)["response"];
+    assert_eq!(redirect["status"], 301);
+    assert_eq!(redirect["redirectURL"], PAGE_URL);
+}
+
+#[test]
+fn test_export_warc() {
+    use chrome_cache_parser::export::warc::{write_warc, WarcExportOptions};
+    use std::io::Read;
+
+    let mut entries = sample_entries();
+    entries.push(FixtureEntry::new(
+        "https://example.com/broken",
+        vec![b"not a pickle".to_vec(), b"evidence".to_vec()],
+    ));
+    let cache = build_cache(&entries, &[]);
+    let mut out = Vec::new();
+    let options = WarcExportOptions { gzip: true };
+    assert_eq!(write_warc(&cache.open(), &mut out, &options).unwrap(), 4);
+
+    let mut warc = Vec::new();
+    flate2::read::MultiGzDecoder::new(&out[..])
+        .read_to_end(&mut warc)
+        .unwrap();
+
+    // Split the records by their `Content-Length`.
+    let mut records = Vec::new();
+    let mut rest = &warc[..];
+    while !rest.is_empty() {
+        let end = rest.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        let headers = String::from_utf8(rest[..end].to_vec()).unwrap();
+        let length = headers
+            .lines()
+            .find_map(|line| line.strip_prefix("Content-Length: "))
+            .unwrap()
+            .parse::<usize>()
+            .unwrap();
+        let block = &rest[end + 4..end + 4 + length];
+        assert_eq!(&rest[end + 4 + length..end + 8 + length], b"\r\n\r\n");
+        rest = &rest[end + 8 + length..];
+        records.push((headers, block));
+    }
+
+    let types = records
+        .iter()
+        .map(|(headers, _)| {
+            headers
+                .lines()
+                .find_map(|line| line.strip_prefix("WARC-Type: "))
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(types.len(), 9);
+    assert_eq!(types[0], "warcinfo");
+    assert!(types[1..]
+        .chunks(2)
+        .all(|pair| pair == ["response", "metadata"]));
+
+    let record = |kind: &str, uri: &str| {
+        records
+            .iter()
+            .find(|(headers, _)| {
+                headers.contains(&format!("WARC-Type: {}\r\n", kind))
+                    && headers.contains(&format!("WARC-Target-URI: {}\r\n", uri))
+            })
+            .unwrap()
+    };
+    let (headers, block) = record("response", LOGO_KEY);
+    assert!(headers.contains("WARC-IP-Address: 93.184.215.14\r\n"));
+    assert!(block.starts_with(b"HTTP/1.1 200 OK\r\n"));
+    assert!(block.ends_with(&logo_body()));
+
+    // The page is stored as it was received, gzipped.
+    let (_, block) = record("response", PAGE_URL);
+    assert!(block.ends_with(&gzip(&page_body())));
+
+    let (_, block) = record("metadata", PAGE_URL);
+    let fields = std::str::from_utf8(block).unwrap();
+    assert!(fields.contains(&format!("cache-key: {}\r\n", PAGE_KEY)));
+    assert!(fields.contains("remote-endpoint: 93.184.215.14:443\r\n"));
+}
+
+#[test]
+fn test_export_timeline() {
+    use chrome_cache_parser::export::timeline::{
+        timeline_events, write_bodyfile, write_l2t_csv, TimelineEventKind,
+    };
+
+    let fixture = build_cache(&sample_entries(), &[]);
+    let cache = fixture.open();
+
+    // Five times per entry: created, request sent, response received, last modified, last used.
+    let events = timeline_events(&cache).unwrap();
+    assert_eq!(events.len(), 20);
+    assert!(events.windows(2).all(|pair| pair[0].time <= pair[1].time));
+    let last = events.last().unwrap();
+    assert_eq!(last.kind, TimelineEventKind::LastUsed);
+    assert_eq!(last.time.timestamp(), RESPONSE_TIME + 60);
+
+    let mut bodyfile = Vec::new();
+    assert_eq!(write_bodyfile(&cache, &mut bodyfile).unwrap(), 4);
+    let bodyfile = String::from_utf8(bodyfile).unwrap();
+    let logo = bodyfile
+        .lines()
+        .find(|line| line.contains(LOGO_KEY))
+        .unwrap();
+    assert_eq!(
+        logo.split('|').skip(6).collect::<Vec<_>>(),
+        [
+            logo_body().len().to_string(),
+            (RESPONSE_TIME + 60).to_string(),
+            (RESPONSE_TIME + 30).to_string(),
+            RESPONSE_TIME.to_string(),
+            (RESPONSE_TIME - 1).to_string(),
+        ]
+    );
+
+    let mut csv = Vec::new();
+    assert_eq!(write_l2t_csv(&cache, &mut csv).unwrap(), 20);
+    let csv = String::from_utf8(csv).unwrap();
+    assert!(csv.starts_with("date,time,timezone,MACB,"));
+    assert_eq!(csv.lines().count(), 21);
+    let rows = csv
+        .lines()
+        .filter(|line| {
+            line.contains(",Last Used Time,") && line.contains(&format!(",{},", PAGE_URL))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(rows.len(), 1);
+    assert!(rows[0].starts_with("11/14/2023,22:14:20,UTC,.A..,WEBHIST,"));
+    assert!(rows[0].contains("remote_endpoint: 93.184.215.14:443"));
+}
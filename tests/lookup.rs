@@ -0,0 +1,42 @@
+//! Looks the entries of caches written to disk up by key.
+mod common;
+
+use chrome_cache_parser::hash::super_fast_hash;
+use common::*;
+
+#[test]
+fn test_lookup() {
+    let fixture = sample_cache();
+    let cache = fixture.open();
+
+    for key in [PAGE_KEY, LOGO_KEY, SCRIPT_KEY, REDIRECT_KEY] {
+        let entry = cache.lookup(key).unwrap().unwrap();
+        assert_eq!(entry.key().unwrap(), key);
+    }
+    assert!(cache
+        .lookup("https://example.com/missing")
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_lookup_skips_unreadable_entries() {
+    // A key in the same bucket as the logo, chained before it, whose long key is in a missing
+    // block file.
+    let bucket = super_fast_hash(LOGO_KEY.as_bytes()) & 0xff;
+    let key = (0..)
+        .map(|n| format!("https://example.com/{}", n))
+        .find(|key| super_fast_hash(key.as_bytes()) & 0xff == bucket)
+        .unwrap();
+    let mut unreadable = FixtureEntry::new(&key, Vec::new());
+    unreadable.long_key = 0xa009_0001;
+
+    let mut entries = vec![unreadable];
+    entries.extend(sample_entries());
+    let fixture = build_cache(&entries, &[]);
+    let cache = fixture.open();
+
+    assert!(cache.lookup(&key).unwrap().is_none());
+    let entry = cache.lookup(LOGO_KEY).unwrap().unwrap();
+    assert_eq!(entry.key().unwrap(), LOGO_KEY);
+}
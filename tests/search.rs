@@ -0,0 +1,135 @@
+//! Searches the entries of caches written to disk.
+mod common;
+
+use chrome_cache_parser::{
+    rules,
+    search::{self, SearchMatch, SearchOptions, SearchTarget},
+    CCPError,
+};
+use common::*;
+use regex::bytes::Regex;
+
+fn search_cache(pattern: &Regex, options: &SearchOptions) -> Vec<SearchMatch> {
+    let fixture = sample_cache();
+    let mut matches = Vec::new();
+    let found = search::search(&fixture.open(), pattern, options, |found| {
+        matches.push(found);
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(found, matches.len());
+    matches
+}
+
+#[test]
+fn test_search() {
+    // Bodies are searched as decoded from their `Content-Encoding`.
+    let bodies = SearchOptions {
+        keys: false,
+        headers: false,
+        bodies: true,
+    };
+    let matches = search_cache(&Regex::new("hello world").unwrap(), &bodies);
+    assert_eq!(matches.len(), 200);
+    assert!(matches.iter().all(|found| found.key == PAGE_KEY
+        && found.target == SearchTarget::Body
+        && found.stream == Some(1)));
+    let head = b"<!doctype html><html><head><title>Example</title></head><body>";
+    assert_eq!(matches[0].offset, head.len());
+
+    let pattern = search::literal_pattern(b"secret-token-1234").unwrap();
+    let matches = search_cache(&pattern, &SearchOptions::default());
+    assert_eq!(matches.len(), 50);
+    assert!(matches.iter().all(|found| found.key == SCRIPT_KEY));
+    assert_eq!(matches[1].offset, script_body().len() / 50 + 27);
+    assert_eq!(matches[1].matched, b"secret-token-1234");
+
+    let matches = search_cache(
+        &Regex::new("max-age=[0-9]+").unwrap(),
+        &SearchOptions::default(),
+    );
+    assert_eq!(
+        matches
+            .iter()
+            .map(|found| (found.key.as_str(), found.target, found.stream))
+            .collect::<Vec<_>>(),
+        [(PAGE_KEY, SearchTarget::Headers, Some(0))]
+    );
+
+    let keys = SearchOptions {
+        keys: true,
+        headers: false,
+        bodies: false,
+    };
+    let matches = search_cache(&Regex::new("(?i)EXAMPLE\\.com/(old|app)").unwrap(), &keys);
+    let mut found = matches
+        .iter()
+        .map(|found| (found.key.as_str(), found.offset))
+        .collect::<Vec<_>>();
+    found.sort();
+    assert_eq!(found, [(SCRIPT_KEY, 8), (REDIRECT_KEY, 8)]);
+    assert!(matches.iter().all(|found| found.stream.is_none()));
+}
+
+#[test]
+fn test_search_v3() {
+    let cache = v3_cache();
+    let pattern = search::literal_pattern(b"secret").unwrap();
+    let result = search::search(&cache.open(), &pattern, &SearchOptions::default(), |_| {
+        Ok(())
+    });
+    assert!(matches!(result, Err(CCPError::UnsupportedVersion(_))));
+}
+
+#[test]
+fn test_scan_v3() {
+    let cache = v3_cache();
+    let rules = rules::parse_rules("rule any { condition: true }").unwrap();
+    let result = rules::scan(&cache.open(), &rules, |_| Ok(()));
+    assert!(matches!(result, Err(CCPError::UnsupportedVersion(_))));
+}
+
+#[test]
+fn test_scan() {
+    let rules = rules::parse_rules(
+        r#"
+        rule token : secrets {
+            strings:
+                $token = /secret-token-[0-9]{4}/
+            condition:
+                #token >= 10
+        }
+        rule png { strings: $magic = { 89 50 4E 47 } condition: $magic }
+        rule greeting { strings: $a = "HELLO WORLD" nocase condition: $a and filesize > 2KB }
+        "#,
+    )
+    .unwrap();
+    let fixture = sample_cache();
+
+    let mut matches = Vec::new();
+    let found = rules::scan(&fixture.open(), &rules, |found| {
+        matches.push(found);
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(found, 3);
+
+    matches.sort_by(|a, b| a.rule.cmp(&b.rule));
+    let summary = matches
+        .iter()
+        .map(|found| (found.rule.as_str(), found.key.as_str()))
+        .collect::<Vec<_>>();
+    // The page only matches once decoded from gzip.
+    assert_eq!(
+        summary,
+        [
+            ("greeting", PAGE_KEY),
+            ("png", LOGO_KEY),
+            ("token", SCRIPT_KEY)
+        ]
+    );
+    assert_eq!(matches[2].tags, ["secrets"]);
+    assert_eq!(matches[2].strings[0].identifier, "$token");
+    assert_eq!(matches[2].strings[0].offsets.len(), 50);
+    assert_eq!(matches[1].strings[0].offsets, [0]);
+}
@@ -0,0 +1,93 @@
+//! Locates the entries and streams of caches written to disk within their files.
+mod common;
+
+use std::{fs, io::Read};
+
+use chrome_cache_parser::{hash::super_fast_hash, FileType};
+use common::*;
+
+#[test]
+fn test_entry_location() {
+    let fixture = sample_cache();
+    let cache = fixture.open();
+    let entry = cache.lookup(LOGO_KEY).unwrap().unwrap();
+
+    let location = entry.location();
+    let addr = location.addr;
+    assert_eq!(addr.file_type(), FileType::Block256);
+    assert_eq!(location.path, fixture.path.join(addr.file_name()));
+    assert_eq!(
+        location.offset,
+        BLOCK_HEADER_SIZE + addr.start_block() as usize * 256
+    );
+    assert_eq!(location.block, Some(addr.start_block()));
+    assert_eq!(
+        location.to_string(),
+        format!(
+            "{} @ 0x{:x} (block {}) [addr 0x{:08x}]",
+            location.path.display(),
+            location.offset,
+            addr.start_block(),
+            addr.value
+        )
+    );
+
+    // The entry record starts with the hash of its key.
+    let data = fs::read(&location.path).unwrap();
+    assert_eq!(
+        data[location.offset..location.offset + 4],
+        super_fast_hash(LOGO_KEY.as_bytes()).to_le_bytes()
+    );
+}
+
+#[test]
+fn test_stream_locations() {
+    let big_body = vec![0x5a; 20_000];
+    let mut entries = sample_entries();
+    entries.push(FixtureEntry::new(
+        "https://example.com/big.bin",
+        vec![Vec::new(), big_body.clone()],
+    ));
+    let fixture = build_cache(&entries, &[]);
+    let cache = fixture.open();
+
+    let entry = cache.lookup(LOGO_KEY).unwrap().unwrap();
+    let locations = entry.stream_locations().unwrap();
+    assert!(locations[2].is_none() && locations[3].is_none());
+    // The stream readers don't carry their location, but read the bytes found there.
+    for (stream, location) in locations.iter().enumerate().take(2) {
+        let location = location.as_ref().unwrap();
+        let addr = location.addr;
+        let block_size = addr.file_type().block_size().unwrap();
+        assert_eq!(location.path, fixture.path.join(addr.file_name()));
+        assert_eq!(
+            location.offset,
+            BLOCK_HEADER_SIZE + addr.start_block() as usize * block_size
+        );
+
+        let mut stored = Vec::new();
+        entry
+            .stream_reader(stream)
+            .unwrap()
+            .read_to_end(&mut stored)
+            .unwrap();
+        let data = fs::read(&location.path).unwrap();
+        assert_eq!(
+            data[location.offset..location.offset + stored.len()],
+            stored
+        );
+    }
+
+    // External files aren't split into blocks.
+    let entry = cache
+        .lookup("https://example.com/big.bin")
+        .unwrap()
+        .unwrap();
+    let locations = entry.stream_locations().unwrap();
+    assert!(locations[0].is_none());
+    let location = locations[1].as_ref().unwrap();
+    assert_eq!(location.addr.file_type(), FileType::External);
+    assert_eq!(location.path, fixture.path.join("f_000001"));
+    assert_eq!((location.offset, location.block), (0, None));
+    assert_eq!(fs::read(&location.path).unwrap(), big_body);
+}